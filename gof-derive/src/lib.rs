@@ -0,0 +1,401 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, parse_macro_input, Data, DeriveInput, Fields, Member, Path, Token};
+
+// Generates `Control`, the accept trait, the `VisitMut` and `Visit` traits, and a helper macro
+// for `#[derive(Visitable)]` for the listed element types in the invoking module.
+// A composite element is marked by `{ .. }`, and the field holding its children by `#[visitable(children)]`.
+// An element marked by `-> Control` has visit methods returning `Control`, so that a visitor can stop
+// the traversal from them as well as from `enter_*`. A trailing `..` leaves the hierarchy open, so that
+// the visitor traits have a `visit_other` method for element kinds added elsewhere.
+// The trait names default to `Accept`, `VisitMut` and `Visit`, and may be renamed before the element types,
+// where `control` names an existing `Control` to use instead of generating one, such as one shared by several hierarchies.
+//
+// visitor! {
+//     accept = Element, visit_mut = Visitor, control = crate::visitor::Control;
+//     ElementA,
+//     ElementB -> Control,
+//     CompositeElement { .. },
+//     ..
+// }
+//
+// NOTE: `visitor!` can be invoked once per module, because it defines the helper macro `__visitable`.
+#[proc_macro]
+pub fn visitor(input: TokenStream) -> TokenStream {
+    let Visitor { names, nodes, open } = parse_macro_input!(input as Visitor);
+    let Names { accept, visit_mut, visit, control: given_control } = &names;
+    let control = match given_control {
+        Some(control) => quote! { #control },
+        None => quote! { Control },
+    };
+
+    let mut visit_mut_methods = Vec::new();
+    let mut visit_methods = Vec::new();
+    let mut rules = Vec::new();
+    for node in nodes.iter() {
+        let ty = &node.ty;
+        let visit_method = method_ident("visit", ty);
+        let (output, body, call) = if node.stops {
+            (quote! { -> #control }, quote! { #control::Continue }, quote! { visitor.#visit_method(self) })
+        } else {
+            (quote! {}, quote! {}, quote! { visitor.#visit_method(self); #control::Continue })
+        };
+        visit_mut_methods.push(quote! {
+            fn #visit_method(&mut self, _node: &mut #ty) #output {
+                #body
+            }
+        });
+        visit_methods.push(quote! {
+            fn #visit_method(&mut self, _node: &#ty) #output {
+                #body
+            }
+        });
+
+        if !node.composite {
+            let message = format!("`{ty}` is a leaf in `visitor!`, so no field can be marked by `#[visitable(children)]`");
+            rules.push(quote! {
+                (#ty) => {
+                    impl #accept for #ty {
+                        fn accept_at(&mut self, visitor: &mut dyn #visit_mut, _depth: usize) -> #control {
+                            #call
+                        }
+
+                        fn accept_ref_at(&self, visitor: &mut dyn #visit, _depth: usize) -> #control {
+                            #call
+                        }
+                    }
+                };
+                (#ty $children:tt) => {
+                    compile_error!(#message);
+                };
+            });
+            continue;
+        }
+
+        let enter = method_ident("enter", ty);
+        let leave = method_ident("leave", ty);
+        visit_mut_methods.push(quote! {
+            fn #enter(&mut self, _node: &mut #ty, _depth: usize) -> #control {
+                #control::Continue
+            }
+
+            fn #leave(&mut self, _node: &mut #ty, _depth: usize) {}
+        });
+        visit_methods.push(quote! {
+            fn #enter(&mut self, _node: &#ty, _depth: usize) -> #control {
+                #control::Continue
+            }
+
+            fn #leave(&mut self, _node: &#ty, _depth: usize) {}
+        });
+
+        // NOTE: A composite stopped by its visit method is not left, like one stopped by its children.
+        let visit_call = if node.stops {
+            quote! {
+                if visitor.#visit_method(self) == #control::Stop {
+                    return #control::Stop;
+                }
+            }
+        } else {
+            quote! { visitor.#visit_method(self); }
+        };
+        let walk = method_ident("walk", ty);
+        let walk_ref = Ident::new(&format!("{walk}_ref"), Span::call_site());
+        let message = format!("`{ty}` is a composite in `visitor!`, so the field holding its children must be marked by `#[visitable(children)]`");
+        rules.push(quote! {
+            (#ty $children:tt) => {
+                impl #accept for #ty {
+                    fn accept_at(&mut self, visitor: &mut dyn #visit_mut, depth: usize) -> #control {
+                        match visitor.#enter(self, depth) {
+                            #control::Stop => return #control::Stop,
+                            #control::SkipChildren => {},
+                            #control::Continue => {
+                                if #walk(visitor, self, depth) == #control::Stop {
+                                    return #control::Stop;
+                                }
+                            },
+                        }
+                        #visit_call
+                        visitor.#leave(self, depth);
+                        #control::Continue
+                    }
+
+                    fn accept_ref_at(&self, visitor: &mut dyn #visit, depth: usize) -> #control {
+                        match visitor.#enter(self, depth) {
+                            #control::Stop => return #control::Stop,
+                            #control::SkipChildren => {},
+                            #control::Continue => {
+                                if #walk_ref(visitor, self, depth) == #control::Stop {
+                                    return #control::Stop;
+                                }
+                            },
+                        }
+                        #visit_call
+                        visitor.#leave(self, depth);
+                        #control::Continue
+                    }
+                }
+
+                pub fn #walk(visitor: &mut dyn #visit_mut, node: &mut #ty, depth: usize) -> #control {
+                    for child in node.$children.iter_mut() {
+                        if child.accept_at(visitor, depth + 1) == #control::Stop {
+                            return #control::Stop;
+                        }
+                    }
+                    #control::Continue
+                }
+
+                pub fn #walk_ref(visitor: &mut dyn #visit, node: &#ty, depth: usize) -> #control {
+                    for child in node.$children.iter() {
+                        if child.accept_ref_at(visitor, depth + 1) == #control::Stop {
+                            return #control::Stop;
+                        }
+                    }
+                    #control::Continue
+                }
+            };
+            (#ty) => {
+                compile_error!(#message);
+            };
+        });
+    }
+
+    if open {
+        visit_mut_methods.push(quote! {
+            fn visit_other(&mut self, _node: &mut dyn ::std::any::Any) {}
+        });
+        visit_methods.push(quote! {
+            fn visit_other(&mut self, _node: &dyn ::std::any::Any) {}
+        });
+    }
+
+    let control_enum = given_control.is_none().then(|| quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Control {
+            Continue,
+            SkipChildren,
+            Stop,
+        }
+    });
+
+    quote! {
+        #control_enum
+
+        pub trait #accept {
+            fn accept_at(&mut self, visitor: &mut dyn #visit_mut, depth: usize) -> #control;
+            fn accept_ref_at(&self, visitor: &mut dyn #visit, depth: usize) -> #control;
+
+            fn accept(&mut self, visitor: &mut dyn #visit_mut) -> #control {
+                self.accept_at(visitor, 0)
+            }
+
+            fn accept_ref(&self, visitor: &mut dyn #visit) -> #control {
+                self.accept_ref_at(visitor, 0)
+            }
+        }
+
+        pub trait #visit_mut {
+            #(#visit_mut_methods)*
+        }
+
+        pub trait #visit {
+            #(#visit_methods)*
+        }
+
+        #[allow(unused_macros)]
+        macro_rules! __visitable {
+            #(#rules)*
+            ($ty:ident $($children:tt)?) => {
+                compile_error!(concat!("`", stringify!($ty), "` is not listed in `visitor!`"));
+            };
+        }
+    }
+    .into()
+}
+
+// Implements the accept trait generated by `visitor!` for an element type listed in it,
+// through the helper macro `__visitable`. A composite element marks the field holding its children
+// with `#[visitable(children)]`, which is the only place the field is named.
+#[proc_macro_derive(Visitable, attributes(visitable))]
+pub fn derive_visitable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_visitable(&input) {
+        Ok(expanded) => expanded.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_visitable(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(input, "`Visitable` can only be derived for structs"));
+    };
+    // NOTE: `visitor!` generates a visitor method per element type, which cannot take type parameters
+    //       without making the visitor traits generic as well.
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "`Visitable` cannot be derived for generic types"));
+    }
+
+    let mut children: Option<Member> = None;
+    let fields = match &data.fields {
+        Fields::Named(fields) => fields.named.iter().collect(),
+        Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+        Fields::Unit => Vec::new(),
+    };
+    for (index, field) in fields.into_iter().enumerate() {
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("visitable")) {
+            attr.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("children") {
+                    return Err(meta.error("expected `children`"));
+                }
+                if children.is_some() {
+                    return Err(meta.error("only one field can hold the children"));
+                }
+                children = Some(match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(index.into()),
+                });
+                Ok(())
+            })?;
+        }
+    }
+
+    let ty = &input.ident;
+    Ok(quote! {
+        __visitable! { #ty #children }
+    })
+}
+
+struct Visitor {
+    names: Names,
+    nodes: Vec<Node>,
+    open: bool,
+}
+
+impl Parse for Visitor {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let names = input.parse()?;
+        let mut nodes = Vec::new();
+        let mut open = false;
+        while !input.is_empty() {
+            if input.peek(Token![..]) {
+                input.parse::<Token![..]>()?;
+                open = true;
+                if !input.is_empty() {
+                    return Err(input.error("`..` must be the last in `visitor!`"));
+                }
+                break;
+            }
+            nodes.push(input.parse()?);
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(Visitor { names, nodes, open })
+    }
+}
+
+// The names of the generated traits, such as `accept = Element, visit_mut = Visitor;`,
+// and the path of an existing `Control` if any.
+struct Names {
+    accept: Ident,
+    visit_mut: Ident,
+    visit: Ident,
+    control: Option<Path>,
+}
+
+impl Parse for Names {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut names = Names {
+            accept: Ident::new("Accept", Span::call_site()),
+            visit_mut: Ident::new("VisitMut", Span::call_site()),
+            visit: Ident::new("Visit", Span::call_site()),
+            control: None,
+        };
+        if !(input.peek(syn::Ident) && input.peek2(Token![=])) {
+            return Ok(names);
+        }
+        loop {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "accept" => names.accept = input.parse()?,
+                "visit_mut" => names.visit_mut = input.parse()?,
+                "visit" => names.visit = input.parse()?,
+                "control" => names.control = Some(input.parse()?),
+                _ => return Err(syn::Error::new_spanned(key, "expected `accept`, `visit_mut`, `visit` or `control`")),
+            }
+            if input.peek(Token![;]) {
+                input.parse::<Token![;]>()?;
+                return Ok(names);
+            }
+            input.parse::<Token![,]>()?;
+        }
+    }
+}
+
+struct Node {
+    ty: Ident,
+    composite: bool,
+    stops: bool,
+}
+
+impl Parse for Node {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ty: Ident = input.parse()?;
+        if input.peek(Token![<]) {
+            return Err(syn::Error::new_spanned(ty, "`visitor!` does not support generic element types"));
+        }
+        let composite = input.peek(syn::token::Brace);
+        if composite {
+            let content;
+            braced!(content in input);
+            if !content.peek(Token![..]) {
+                return Err(content.error("expected `{ .. }`; the field holding the children is marked by `#[visitable(children)]`"));
+            }
+            content.parse::<Token![..]>()?;
+        }
+        let stops = input.peek(Token![->]);
+        if stops {
+            input.parse::<Token![->]>()?;
+            let output: Ident = input.parse()?;
+            if output != "Control" {
+                return Err(syn::Error::new_spanned(output, "expected `-> Control`; a visit method returns nothing or `Control`"));
+            }
+        }
+        Ok(Node { ty, composite, stops })
+    }
+}
+
+// Makes a method name such as `visit_floppy_disk` from a prefix and a type name such as `FloppyDisk`.
+// An acronym makes one word, so that `CPU` makes `visit_cpu` and `HTTPServer` makes `visit_http_server`.
+fn method_ident(prefix: &str, ty: &Ident) -> Ident {
+    let chars: Vec<char> = ty.to_string().chars().collect();
+    let mut name = format!("{prefix}_");
+    for (i, &c) in chars.iter().enumerate() {
+        if i > 0 && c.is_uppercase() {
+            let prev = chars[i - 1];
+            let next_is_lowercase = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if !prev.is_uppercase() || next_is_lowercase {
+                name.push('_');
+            }
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    Ident::new(&name, ty.span())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_ident_in_snake_case() {
+        let method = |ty: &str| method_ident("visit", &Ident::new(ty, Span::call_site())).to_string();
+        assert_eq!(method("FloppyDisk"), "visit_floppy_disk");
+        assert_eq!(method("CPU"), "visit_cpu");
+        assert_eq!(method("HTTPServer"), "visit_http_server");
+        assert_eq!(method("Int"), "visit_int");
+    }
+}
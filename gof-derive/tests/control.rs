@@ -0,0 +1,94 @@
+use gof_derive::{visitor, Visitable};
+
+mod shared {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Control {
+        Continue,
+        SkipChildren,
+        Stop,
+    }
+}
+
+use shared::Control;
+
+visitor! {
+    accept = Node, visit_mut = NodeVisitor, visit = NodeVisit, control = shared::Control;
+    Leaf -> Control,
+    Branch { .. } -> Control,
+}
+
+#[derive(Visitable)]
+pub struct Leaf(u32);
+
+#[derive(Visitable)]
+pub struct Branch {
+    #[visitable(children)]
+    children: Vec<Box<dyn Node>>,
+}
+
+// Stops at the first leaf above the limit.
+struct Stopper {
+    limit: u32,
+    visited: Vec<u32>,
+    left: usize,
+}
+
+impl Stopper {
+    fn new(limit: u32) -> Self {
+        Stopper { limit, visited: Vec::new(), left: 0 }
+    }
+}
+
+impl NodeVisit for Stopper {
+    fn visit_leaf(&mut self, leaf: &Leaf) -> Control {
+        self.visited.push(leaf.0);
+        if leaf.0 > self.limit { Control::Stop } else { Control::Continue }
+    }
+
+    fn leave_branch(&mut self, _branch: &Branch, _depth: usize) {
+        self.left += 1;
+    }
+}
+
+struct Doubler;
+
+impl NodeVisitor for Doubler {
+    fn visit_leaf(&mut self, leaf: &mut Leaf) -> Control {
+        leaf.0 *= 2;
+        Control::Continue
+    }
+}
+
+struct BranchStopper(usize);
+
+impl NodeVisit for BranchStopper {
+    fn visit_branch(&mut self, _branch: &Branch) -> Control {
+        self.0 += 1;
+        Control::Stop
+    }
+}
+
+#[test]
+fn control() {
+    let mut tree = Branch {
+        children: vec![
+            Box::new(Leaf(1)),
+            Box::new(Branch { children: vec![Box::new(Leaf(5)), Box::new(Leaf(2))] }),
+            Box::new(Leaf(3)),
+        ],
+    };
+
+    let mut stopper = Stopper::new(4);
+    assert_eq!(tree.accept_ref(&mut stopper), Control::Stop);
+    assert_eq!((stopper.visited, stopper.left), (vec![1, 5], 0));
+
+    assert_eq!(tree.accept(&mut Doubler), Control::Continue);
+    let mut stopper = Stopper::new(100);
+    assert_eq!(tree.accept_ref(&mut stopper), Control::Continue);
+    assert_eq!((stopper.visited, stopper.left), (vec![2, 10, 4, 6], 2));
+
+    // The inner branch stops the traversal, so the outer one is neither visited nor left.
+    let mut branch_stopper = BranchStopper(0);
+    assert_eq!(tree.accept_ref(&mut branch_stopper), Control::Stop);
+    assert_eq!(branch_stopper.0, 1);
+}
@@ -0,0 +1,70 @@
+use std::any::Any;
+use gof_derive::{visitor, Visitable};
+
+visitor! {
+    Int,
+    Float,
+    ..
+}
+
+#[derive(Visitable)]
+pub struct Int(i32);
+
+#[derive(Visitable)]
+pub struct Float(f64);
+
+// A kind of number added outside `visitor!`, which the visitors see through `visit_other`.
+pub struct Ratio(i32, i32);
+
+impl Accept for Ratio {
+    fn accept_at(&mut self, visitor: &mut dyn VisitMut, _depth: usize) -> Control {
+        visitor.visit_other(self);
+        Control::Continue
+    }
+
+    fn accept_ref_at(&self, visitor: &mut dyn Visit, _depth: usize) -> Control {
+        visitor.visit_other(self);
+        Control::Continue
+    }
+}
+
+struct Sum(f64);
+
+impl Visit for Sum {
+    fn visit_int(&mut self, int: &Int) {
+        self.0 += int.0 as f64;
+    }
+
+    fn visit_float(&mut self, float: &Float) {
+        self.0 += float.0;
+    }
+
+    fn visit_other(&mut self, node: &dyn Any) {
+        if let Some(ratio) = node.downcast_ref::<Ratio>() {
+            self.0 += ratio.0 as f64 / ratio.1 as f64;
+        }
+    }
+}
+
+// Overrides only `visit_int`, so the other kinds are left unchanged by the default methods.
+struct Negator;
+
+impl VisitMut for Negator {
+    fn visit_int(&mut self, int: &mut Int) {
+        int.0 = -int.0;
+    }
+}
+
+#[test]
+fn open() {
+    let mut numbers: Vec<Box<dyn Accept>> = vec![Box::new(Int(1)), Box::new(Float(2.5)), Box::new(Ratio(1, 4))];
+
+    for n in numbers.iter_mut() {
+        assert_eq!(n.accept(&mut Negator), Control::Continue);
+    }
+    let mut sum = Sum(0.0);
+    for n in numbers.iter() {
+        n.accept_ref(&mut sum);
+    }
+    assert_eq!(sum.0, 1.75);
+}
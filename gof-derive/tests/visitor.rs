@@ -0,0 +1,95 @@
+use gof_derive::{visitor, Visitable};
+
+visitor! {
+    ElementA,
+    ElementB,
+    CompositeElement { .. },
+}
+
+pub trait Element: Accept {}
+
+#[derive(Visitable)]
+pub struct ElementA;
+
+impl Element for ElementA {}
+
+#[derive(Visitable)]
+pub struct ElementB;
+
+impl Element for ElementB {}
+
+#[derive(Visitable)]
+pub struct CompositeElement {
+    #[visitable(children)]
+    children: Vec<Box<dyn Element>>,
+}
+
+impl Element for CompositeElement {}
+
+struct Tracer {
+    events: Vec<String>,
+}
+
+impl Visit for Tracer {
+    fn visit_element_a(&mut self, _element: &ElementA) {
+        self.events.push("a".to_string());
+    }
+
+    fn visit_element_b(&mut self, _element: &ElementB) {
+        self.events.push("b".to_string());
+    }
+
+    fn visit_composite_element(&mut self, _element: &CompositeElement) {
+        self.events.push("composite".to_string());
+    }
+
+    fn enter_composite_element(&mut self, element: &CompositeElement, depth: usize) -> Control {
+        self.events.push(format!("enter {depth}"));
+        if element.children.is_empty() { Control::SkipChildren } else { Control::Continue }
+    }
+
+    fn leave_composite_element(&mut self, _element: &CompositeElement, depth: usize) {
+        self.events.push(format!("leave {depth}"));
+    }
+}
+
+struct CounterB(usize);
+
+impl VisitMut for CounterB {
+    fn visit_element_b(&mut self, _element: &mut ElementB) {
+        self.0 += 1;
+    }
+}
+
+#[test]
+fn visitor() {
+    let mut elements: Vec<Box<dyn Element>> = vec![
+        Box::new(ElementA),
+        Box::new(ElementB),
+        Box::new(CompositeElement {
+            children: vec![
+                Box::new(ElementA),
+                Box::new(CompositeElement { children: vec![Box::new(ElementB)] }),
+            ],
+        }),
+    ];
+
+    let mut tracer = Tracer { events: Vec::new() };
+    for e in elements.iter() {
+        e.accept_ref(&mut tracer);
+    }
+    assert_eq!(tracer.events, vec![
+        "a", "b", "enter 0", "a", "enter 1", "b", "composite", "leave 1", "composite", "leave 0",
+    ]);
+
+    let mut counter = CounterB(0);
+    for e in elements.iter_mut() {
+        e.accept(&mut counter);
+    }
+    assert_eq!(counter.0, 2);
+
+    let mut counter = CounterB(0);
+    let Some(composite) = elements.last_mut() else { unreachable!() };
+    assert_eq!(composite.accept(&mut counter), Control::Continue);
+    assert_eq!(counter.0, 1);
+}
@@ -24,6 +24,11 @@ pub trait Equipment {
     fn parts(&self) -> Box<dyn Iterator<Item = &Box<dyn Equipment + '_>> + '_> {
         Box::new(NoneIterator::new())
     }
+
+    // Tells a chassis without parts apart from a leaf equipment.
+    fn is_composite(&self) -> bool {
+        false
+    }
 }
 
 pub struct FloppyDisk {
@@ -121,6 +126,10 @@ impl Equipment for Chassis {
     fn parts(&self) -> Box<dyn Iterator<Item = &Box<dyn Equipment + '_>> + '_> {
         Box::new(self.parts.iter())
     }
+
+    fn is_composite(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
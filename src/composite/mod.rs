@@ -2,6 +2,7 @@ pub mod exam1;
 
 use std::result;
 use crate::utils::iter::NoneIterator;
+use crate::visitor::{Accept, ByVisitor, Control};
 
 pub type Error = &'static str;
 pub type Result<T> = result::Result<T, Error>;
@@ -13,6 +14,34 @@ pub trait Component {
     fn id(&self) -> Id;
     fn operation(&self);
 
+    // Visits the children at `depth + 1`, so that a component other than `Leaf` and `Composite`,
+    // which the visitors have no callback for, is visitable through its children.
+    fn accept_at(&mut self, visitor: &mut dyn ComponentVisitor, depth: usize) -> Control {
+        for child in self.children_mut() {
+            if child.accept_at(visitor, depth + 1) == Control::Stop {
+                return Control::Stop;
+            }
+        }
+        Control::Continue
+    }
+
+    fn accept_ref_at(&self, visitor: &mut dyn ComponentVisit, depth: usize) -> Control {
+        for child in self.children() {
+            if child.accept_ref_at(visitor, depth + 1) == Control::Stop {
+                return Control::Stop;
+            }
+        }
+        Control::Continue
+    }
+
+    fn accept(&mut self, visitor: &mut dyn ComponentVisitor) -> Control {
+        self.accept_at(visitor, 0)
+    }
+
+    fn accept_ref(&self, visitor: &mut dyn ComponentVisit) -> Control {
+        self.accept_ref_at(visitor, 0)
+    }
+
     // Collection Operations
     fn add(&mut self, #[allow(unused)] child: Box<dyn Component>) -> Result<()> {
         Err("function `Component::add` cannot be applied to a leaf component")
@@ -25,6 +54,10 @@ pub trait Component {
     fn children(&self) -> Box<dyn Iterator<Item = &Box<dyn Component + '_>> + '_> {
         Box::new(NoneIterator::new())
     }
+
+    fn children_mut(&mut self) -> Box<dyn Iterator<Item = &mut Box<dyn Component>> + '_> {
+        Box::new(NoneIterator::new())
+    }
 }
 
 pub struct Leaf {
@@ -43,6 +76,14 @@ impl Component for Leaf {
     }
 
     fn operation(&self) {}
+
+    fn accept_at(&mut self, visitor: &mut dyn ComponentVisitor, depth: usize) -> Control {
+        visitor.visit_leaf(self, depth)
+    }
+
+    fn accept_ref_at(&self, visitor: &mut dyn ComponentVisit, depth: usize) -> Control {
+        visitor.visit_leaf(self, depth)
+    }
 }
 
 pub struct Composite {
@@ -70,6 +111,36 @@ impl Component for Composite {
         }
     }
 
+    fn accept_at(&mut self, visitor: &mut dyn ComponentVisitor, depth: usize) -> Control {
+        match visitor.enter_composite(self, depth) {
+            Control::Stop => return Control::Stop,
+            Control::SkipChildren => {},
+            Control::Continue => {
+                if walk_composite(visitor, self, depth) == Control::Stop {
+                    return Control::Stop;
+                }
+            },
+        }
+        visitor.visit_composite(self);
+        visitor.leave_composite(self, depth);
+        Control::Continue
+    }
+
+    fn accept_ref_at(&self, visitor: &mut dyn ComponentVisit, depth: usize) -> Control {
+        match visitor.enter_composite(self, depth) {
+            Control::Stop => return Control::Stop,
+            Control::SkipChildren => {},
+            Control::Continue => {
+                if walk_composite_ref(visitor, self, depth) == Control::Stop {
+                    return Control::Stop;
+                }
+            },
+        }
+        visitor.visit_composite(self);
+        visitor.leave_composite(self, depth);
+        Control::Continue
+    }
+
     fn add(&mut self, child: Box<dyn Component>) -> Result<()> {
         self.children.push(child);
         Ok(())
@@ -89,11 +160,82 @@ impl Component for Composite {
     fn children(&self) -> Box<dyn Iterator<Item = &Box<dyn Component + '_>> + '_> {
         Box::new(self.children.iter())
     }
+
+    fn children_mut(&mut self) -> Box<dyn Iterator<Item = &mut Box<dyn Component>> + '_> {
+        Box::new(self.children.iter_mut())
+    }
+}
+
+// Visits the components of a tree, each composite after its children as in `visitor::exam1`.
+// A leaf returns `Control::Stop` to stop the traversal, and `Control::SkipChildren` is the same as `Control::Continue` for it.
+pub trait ComponentVisitor {
+    fn visit_leaf(&mut self, _leaf: &mut Leaf, _depth: usize) -> Control {
+        Control::Continue
+    }
+
+    fn visit_composite(&mut self, _composite: &mut Composite) {}
+
+    // Hooks
+    fn enter_composite(&mut self, _composite: &mut Composite, _depth: usize) -> Control {
+        Control::Continue
+    }
+
+    fn leave_composite(&mut self, _composite: &mut Composite, _depth: usize) {}
+}
+
+pub trait ComponentVisit {
+    fn visit_leaf(&mut self, _leaf: &Leaf, _depth: usize) -> Control {
+        Control::Continue
+    }
+
+    fn visit_composite(&mut self, _composite: &Composite) {}
+
+    // Hooks
+    fn enter_composite(&mut self, _composite: &Composite, _depth: usize) -> Control {
+        Control::Continue
+    }
+
+    fn leave_composite(&mut self, _composite: &Composite, _depth: usize) {}
+}
+
+impl<V: ComponentVisitor> Accept<V, ByVisitor> for &mut Box<dyn Component + '_> {
+    type Output = Control;
+
+    fn accept_with(self, visitor: &mut V) -> Control {
+        self.accept(visitor)
+    }
+}
+
+impl<V: ComponentVisit> Accept<V, ByVisitor> for &Box<dyn Component + '_> {
+    type Output = Control;
+
+    fn accept_with(self, visitor: &mut V) -> Control {
+        self.accept_ref(visitor)
+    }
+}
+
+pub fn walk_composite(visitor: &mut dyn ComponentVisitor, composite: &mut Composite, depth: usize) -> Control {
+    for child in composite.children.iter_mut() {
+        if child.accept_at(visitor, depth + 1) == Control::Stop {
+            return Control::Stop;
+        }
+    }
+    Control::Continue
+}
+
+pub fn walk_composite_ref(visitor: &mut dyn ComponentVisit, composite: &Composite, depth: usize) -> Control {
+    for child in composite.children.iter() {
+        if child.accept_ref_at(visitor, depth + 1) == Control::Stop {
+            return Control::Stop;
+        }
+    }
+    Control::Continue
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::visitor::AcceptAll;
 
     #[test]
     fn composite() {
@@ -109,4 +251,101 @@ mod tests {
         composite2.remove(5).unwrap();
         assert_eq!(composite2.children().count(), 2);
     }
+
+    #[test]
+    fn composite_with_visitor() {
+        // Traces the ids with the depths of the composites, and skips the children of the composites deeper than `max_depth`.
+        // Stops at the leaf of id `stop_at`.
+        struct Tracer {
+            events: Vec<String>,
+            max_depth: usize,
+            stop_at: Id,
+        }
+
+        impl ComponentVisit for Tracer {
+            fn visit_leaf(&mut self, leaf: &Leaf, depth: usize) -> Control {
+                self.events.push(format!("leaf {} at {depth}", leaf.id()));
+                if leaf.id() == self.stop_at { Control::Stop } else { Control::Continue }
+            }
+
+            fn visit_composite(&mut self, composite: &Composite) {
+                self.events.push(format!("composite {}", composite.id()));
+            }
+
+            fn enter_composite(&mut self, composite: &Composite, depth: usize) -> Control {
+                self.events.push(format!("enter {} at {depth}", composite.id()));
+                if depth < self.max_depth { Control::Continue } else { Control::SkipChildren }
+            }
+
+            fn leave_composite(&mut self, composite: &Composite, depth: usize) {
+                self.events.push(format!("leave {} at {depth}", composite.id()));
+            }
+        }
+
+        struct Counter(usize);
+
+        impl ComponentVisitor for Counter {
+            fn visit_leaf(&mut self, _leaf: &mut Leaf, _depth: usize) -> Control {
+                self.0 += 1;
+                Control::Continue
+            }
+
+            fn enter_composite(&mut self, _composite: &mut Composite, _depth: usize) -> Control {
+                if self.0 < 2 { Control::Continue } else { Control::Stop }
+            }
+        }
+
+        let mut composite1 = Composite::new(1);
+        composite1.add(Box::new(Leaf::new(2))).unwrap();
+        let mut composite2 = Composite::new(3);
+        composite2.add(Box::new(Leaf::new(4))).unwrap();
+        composite2.add(Box::new(composite1)).unwrap();
+        composite2.add(Box::new(Leaf::new(5))).unwrap();
+
+        let mut v = Tracer { events: Vec::new(), max_depth: 1, stop_at: 0 };
+        assert_eq!(composite2.accept_ref(&mut v), Control::Continue);
+        assert_eq!(v.events, vec!["enter 3 at 0", "leaf 4 at 1", "enter 1 at 1", "composite 1", "leave 1 at 1", "leaf 5 at 1", "composite 3", "leave 3 at 0"]);
+        let mut v = Tracer { events: Vec::new(), max_depth: 2, stop_at: 2 };
+        assert_eq!(composite2.accept_ref(&mut v), Control::Stop);
+        assert_eq!(v.events, vec!["enter 3 at 0", "leaf 4 at 1", "enter 1 at 1", "leaf 2 at 2"]);
+
+        let mut v = Counter(1);
+        assert_eq!(composite2.accept(&mut v), Control::Stop);
+        assert_eq!(v.0, 2);
+        let mut leaf: Box<dyn Component> = Box::new(Leaf::new(6));
+        assert_eq!(leaf.accept(&mut v), Control::Continue);
+        assert_eq!(v.0, 3);
+
+        let mut components: Vec<Box<dyn Component>> = vec![Box::new(composite2), leaf];
+        assert_eq!(components.iter_mut().visit_with(Counter(0)).0, 4);
+        let v = components.iter().visit_with(Tracer { events: Vec::new(), max_depth: 0, stop_at: 0 });
+        assert_eq!(v.events, vec!["enter 3 at 0", "composite 3", "leave 3 at 0", "leaf 6 at 0"]);
+
+        // A component of its own is visited through its children by the default `accept_at` and `accept_ref_at`.
+        struct Pair(Box<dyn Component>, Box<dyn Component>);
+
+        impl Component for Pair {
+            fn id(&self) -> Id {
+                0
+            }
+
+            fn operation(&self) {}
+
+            fn children(&self) -> Box<dyn Iterator<Item = &Box<dyn Component + '_>> + '_> {
+                Box::new([&self.0, &self.1].into_iter())
+            }
+
+            fn children_mut(&mut self) -> Box<dyn Iterator<Item = &mut Box<dyn Component>> + '_> {
+                Box::new([&mut self.0, &mut self.1].into_iter())
+            }
+        }
+
+        let mut pair = Pair(Box::new(Leaf::new(7)), components.pop().unwrap());
+        let mut v = Tracer { events: Vec::new(), max_depth: 0, stop_at: 0 };
+        assert_eq!(pair.accept_ref(&mut v), Control::Continue);
+        assert_eq!(v.events, vec!["leaf 7 at 1", "leaf 6 at 1"]);
+        let mut v = Counter(0);
+        assert_eq!(pair.accept(&mut v), Control::Continue);
+        assert_eq!(v.0, 2);
+    }
 }
@@ -10,6 +10,12 @@ impl<T> NoneIterator<T> {
     }
 }
 
+impl<T> Default for NoneIterator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Iterator for NoneIterator<T> {
     type Item = T;
 
@@ -1 +1,3 @@
-pub mod iter;
\ No newline at end of file
+pub mod iter;
+pub mod num;
+pub mod stats;
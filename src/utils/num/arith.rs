@@ -0,0 +1,455 @@
+use crate::utils::num::{BigInt, Complex, Decimal, Rational};
+use crate::utils::stats::CompensatedSum;
+
+// A number of any kind, as produced by the kernels below.
+// The visitor examples convert it into their own representation of numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Float(f64),
+    BigInt(BigInt),
+    Rational(Rational),
+    Decimal(Decimal),
+    Complex(Complex),
+}
+
+impl Value {
+    // Returns `NaN` for a `Complex` which is not real.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Value::Int(int) => *int as f64,
+            Value::Float(float) => *float,
+            Value::BigInt(big_int) => big_int.to_f64(),
+            Value::Rational(rational) => rational.to_f64(),
+            Value::Decimal(decimal) => decimal.to_f64(),
+            Value::Complex(complex) if complex.is_real() => complex.re,
+            Value::Complex(_) => f64::NAN,
+        }
+    }
+}
+
+// Decides what happens when an `Int` or a `BigInt` shifted by a step is not an integer of its kind,
+// because the result overflows or the step is fractional.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    // Leaves the number unchanged and reports an error.
+    Checked,
+    // Clamps the result to the range of `i32`, truncating the fraction.
+    Saturating,
+    // Wraps the result around the range of `i32`, truncating the fraction.
+    Wrapping,
+    // Promotes an `Int` to `Float` and a `BigInt` to `Decimal`. A number can change its kind only by `fold`,
+    // so visiting in place falls back to `Checked`.
+    Promote,
+}
+
+// The result of shifting an integer of kind `T`, which may be promoted to the kind `P`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shifted<T, P> {
+    Kept(T),
+    Promoted(P),
+    Overflow,
+}
+
+pub fn shift_int(int: i32, step: f64, overflow: Overflow) -> Shifted<i32, f64> {
+    let exact = int as f64 + step;
+    if exact.fract() == 0.0 && exact >= i32::MIN as f64 && exact <= i32::MAX as f64 {
+        return Shifted::Kept(exact as i32);
+    }
+    match overflow {
+        Overflow::Checked => Shifted::Overflow,
+        Overflow::Saturating if !exact.is_nan() => Shifted::Kept(exact as i32),
+        Overflow::Wrapping if exact.is_finite() => Shifted::Kept(exact.trunc().rem_euclid(4294967296.0) as u32 as i32),
+        Overflow::Promote => Shifted::Promoted(exact),
+        _ => Shifted::Overflow,
+    }
+}
+
+// `BigInt` has no range to overflow, so only a fractional step is subject to the policy.
+// A step which is not finite cannot shift it under any policy.
+pub fn shift_big_int(big_int: &BigInt, step: f64, overflow: Overflow) -> Shifted<BigInt, Decimal> {
+    let Some(step) = Decimal::from_f64(step) else {
+        return Shifted::Overflow;
+    };
+    let exact = &Decimal::from(big_int.clone()) + &step;
+    let truncated = exact.trunc();
+    if Decimal::from(truncated.clone()) == exact {
+        return Shifted::Kept(truncated);
+    }
+    match overflow {
+        Overflow::Checked => Shifted::Overflow,
+        Overflow::Saturating | Overflow::Wrapping => Shifted::Kept(truncated),
+        Overflow::Promote => Shifted::Promoted(exact),
+    }
+}
+
+// Returns `None` if `step` is not finite.
+pub fn shift_rational(rational: &Rational, step: f64) -> Option<Rational> {
+    Decimal::from_f64(step).map(|step| rational + &step.to_rational())
+}
+
+// Returns `None` if `step` is not finite.
+pub fn shift_decimal(decimal: &Decimal, step: f64) -> Option<Decimal> {
+    Decimal::from_f64(step).map(|step| decimal + &step)
+}
+
+// Shifts a number of any kind by the `shift_*` function of its kind, where `Float` and `Complex` are always kept.
+pub fn shift(value: &Value, step: f64, overflow: Overflow) -> Shifted<Value, Value> {
+    let exact = |shifted: Option<Value>| shifted.map_or(Shifted::Overflow, Shifted::Kept);
+    match value {
+        Value::Int(int) => match shift_int(*int, step, overflow) {
+            Shifted::Kept(shifted) => Shifted::Kept(Value::Int(shifted)),
+            Shifted::Promoted(promoted) => Shifted::Promoted(Value::Float(promoted)),
+            Shifted::Overflow => Shifted::Overflow,
+        },
+        Value::Float(float) => Shifted::Kept(Value::Float(float + step)),
+        Value::BigInt(big_int) => match shift_big_int(big_int, step, overflow) {
+            Shifted::Kept(shifted) => Shifted::Kept(Value::BigInt(shifted)),
+            Shifted::Promoted(promoted) => Shifted::Promoted(Value::Decimal(promoted)),
+            Shifted::Overflow => Shifted::Overflow,
+        },
+        Value::Rational(rational) => exact(shift_rational(rational, step).map(Value::Rational)),
+        Value::Decimal(decimal) => exact(shift_decimal(decimal, step).map(Value::Decimal)),
+        Value::Complex(complex) => Shifted::Kept(Value::Complex(Complex::new(complex.re + step, complex.im))),
+    }
+}
+
+// Reports the number left unchanged, in the representation `N` of the visitor example which shifted it.
+// A step which is not finite cannot shift the exact kinds, `BigInt`, `Rational` and `Decimal`, under any policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverflowError<N> {
+    pub number: N,
+    pub step: f64,
+}
+
+// Shifts numbers by a step under an overflow policy, and collects the errors of the numbers left unchanged.
+// The increasers and decreasers of the visitor examples visit and fold their numbers by it.
+#[derive(Debug, Clone)]
+pub struct Shifter<N> {
+    step: f64,
+    overflow: Overflow,
+    errors: Vec<OverflowError<N>>,
+}
+
+impl<N: From<Value>> Shifter<N> {
+    pub fn new(step: f64, overflow: Overflow) -> Self {
+        Shifter {
+            step,
+            overflow,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn errors(&self) -> &[OverflowError<N>] {
+        &self.errors
+    }
+
+    // Returns a shifter by the same step and policy without the errors.
+    pub fn split(&self) -> Self {
+        Self::new(self.step, self.overflow)
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.errors.extend(other.errors);
+    }
+
+    // Returns the shifted number, which may be promoted to another kind, or `value` unchanged on an error.
+    pub fn shift(&mut self, value: Value) -> Value {
+        match shift(&value, self.step, self.overflow) {
+            Shifted::Kept(shifted) | Shifted::Promoted(shifted) => shifted,
+            Shifted::Overflow => {
+                self.overflowed(value.clone());
+                value
+            },
+        }
+    }
+
+    // Returns the shifted number of the same kind as `value`, or `None` on an error.
+    // A number visited in place cannot change its kind, so a promotion is an error as under `Overflow::Checked`.
+    pub fn shift_in_place(&mut self, value: Value) -> Option<Value> {
+        match shift(&value, self.step, self.overflow) {
+            Shifted::Kept(shifted) => Some(shifted),
+            Shifted::Promoted(_) | Shifted::Overflow => {
+                self.overflowed(value);
+                None
+            },
+        }
+    }
+
+    fn overflowed(&mut self, value: Value) {
+        self.errors.push(OverflowError { number: value.into(), step: self.step });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+// The `calculate_*` functions calculate `lhs <operator> rhs` of two numbers of the same kind.
+// `Int` with `Int` stays `Int` unless the result overflows or the division is inexact,
+// in which case both are promoted to `BigInt`. `BigInt`, `Rational` and `Decimal` are exact,
+// except that an inexact division of `Int` or `BigInt` or any division of `Decimal` produces `Rational`,
+// and a division of them by zero produces `Float`.
+pub fn calculate_int(operator: Operator, lhs: i32, rhs: i32) -> Value {
+    let result = match operator {
+        Operator::Add => lhs.checked_add(rhs),
+        Operator::Sub => lhs.checked_sub(rhs),
+        Operator::Mul => lhs.checked_mul(rhs),
+        Operator::Div => match lhs.checked_rem(rhs) {
+            Some(0) => lhs.checked_div(rhs),
+            _ => None,
+        },
+    };
+    match result {
+        Some(result) => Value::Int(result),
+        None => calculate_big_int(operator, &BigInt::from(lhs), &BigInt::from(rhs)),
+    }
+}
+
+// Negates an `Int`, which overflows only for `i32::MIN` and is promoted to `BigInt` then, as in `calculate_int`.
+pub fn negate_int(int: i32) -> Value {
+    match int.checked_neg() {
+        Some(negated) => Value::Int(negated),
+        None => Value::BigInt(BigInt::from(-(int as i64))),
+    }
+}
+
+pub fn negate(value: Value) -> Value {
+    match value {
+        Value::Int(int) => negate_int(int),
+        Value::Float(float) => Value::Float(-float),
+        Value::BigInt(big_int) => Value::BigInt(-big_int),
+        Value::Rational(rational) => Value::Rational(-&rational),
+        Value::Decimal(decimal) => Value::Decimal(-&decimal),
+        Value::Complex(complex) => Value::Complex(-complex),
+    }
+}
+
+// Rounds a `Float` to the nearest `Int`, or keeps it if the result does not fit in `i32`.
+pub fn round(float: f64) -> Value {
+    let rounded = float.round();
+    if rounded >= i32::MIN as f64 && rounded <= i32::MAX as f64 {
+        Value::Int(rounded as i32)
+    } else {
+        Value::Float(float)
+    }
+}
+
+pub fn calculate_float(operator: Operator, lhs: f64, rhs: f64) -> Value {
+    let result = match operator {
+        Operator::Add => lhs + rhs,
+        Operator::Sub => lhs - rhs,
+        Operator::Mul => lhs * rhs,
+        Operator::Div => lhs / rhs,
+    };
+    Value::Float(result)
+}
+
+pub fn calculate_big_int(operator: Operator, lhs: &BigInt, rhs: &BigInt) -> Value {
+    let result = match operator {
+        Operator::Add => lhs + rhs,
+        Operator::Sub => lhs - rhs,
+        Operator::Mul => lhs * rhs,
+        Operator::Div => match lhs.div_rem(rhs) {
+            Some((quotient, remainder)) if remainder.is_zero() => quotient,
+            Some(_) => return calculate_rational(operator, &Rational::from(lhs.clone()), &Rational::from(rhs.clone())),
+            None => return calculate_float(operator, lhs.to_f64(), rhs.to_f64()),
+        },
+    };
+    Value::BigInt(result)
+}
+
+pub fn calculate_rational(operator: Operator, lhs: &Rational, rhs: &Rational) -> Value {
+    let result = match operator {
+        Operator::Add => lhs + rhs,
+        Operator::Sub => lhs - rhs,
+        Operator::Mul => lhs * rhs,
+        Operator::Div => match lhs.checked_div(rhs) {
+            Some(result) => result,
+            None => return calculate_float(operator, lhs.to_f64(), rhs.to_f64()),
+        },
+    };
+    Value::Rational(result)
+}
+
+pub fn calculate_decimal(operator: Operator, lhs: &Decimal, rhs: &Decimal) -> Value {
+    let result = match operator {
+        Operator::Add => lhs + rhs,
+        Operator::Sub => lhs - rhs,
+        Operator::Mul => lhs * rhs,
+        Operator::Div => return calculate_rational(operator, &lhs.to_rational(), &rhs.to_rational()),
+    };
+    Value::Decimal(result)
+}
+
+pub fn calculate_complex(operator: Operator, lhs: Complex, rhs: Complex) -> Value {
+    let result = match operator {
+        Operator::Add => lhs + rhs,
+        Operator::Sub => lhs - rhs,
+        Operator::Mul => lhs * rhs,
+        Operator::Div => lhs / rhs,
+    };
+    Value::Complex(result)
+}
+
+// Sums the exact kinds exactly, keeping the integers as `BigInt` so that the sum never overflows,
+// and the real parts of the inexact kinds by compensated summation.
+#[derive(Debug, Clone)]
+pub struct Summation {
+    integer: BigInt,
+    decimal: Option<Decimal>,
+    rational: Option<Rational>,
+    inexact: Option<CompensatedSum>,
+    imaginary: Option<CompensatedSum>,
+}
+
+impl Summation {
+    pub fn new() -> Self {
+        Summation {
+            integer: BigInt::zero(),
+            decimal: None,
+            rational: None,
+            inexact: None,
+            imaginary: None,
+        }
+    }
+
+    pub fn add_int(&mut self, int: i32) {
+        self.integer = &self.integer + &BigInt::from(int);
+    }
+
+    pub fn add_float(&mut self, float: f64) {
+        self.inexact.get_or_insert_default().push(float);
+    }
+
+    pub fn add_big_int(&mut self, big_int: &BigInt) {
+        self.integer = &self.integer + big_int;
+    }
+
+    pub fn add_rational(&mut self, rational: &Rational) {
+        self.rational = Some(match &self.rational {
+            Some(sum) => sum + rational,
+            None => rational.clone(),
+        });
+    }
+
+    pub fn add_decimal(&mut self, decimal: &Decimal) {
+        self.decimal = Some(match &self.decimal {
+            Some(sum) => sum + decimal,
+            None => decimal.clone(),
+        });
+    }
+
+    pub fn add_complex(&mut self, complex: Complex) {
+        self.inexact.get_or_insert_default().push(complex.re);
+        self.imaginary.get_or_insert_default().push(complex.im);
+    }
+
+    pub fn add(&mut self, value: &Value) {
+        match value {
+            Value::Int(int) => self.add_int(*int),
+            Value::Float(float) => self.add_float(*float),
+            Value::BigInt(big_int) => self.add_big_int(big_int),
+            Value::Rational(rational) => self.add_rational(rational),
+            Value::Decimal(decimal) => self.add_decimal(decimal),
+            Value::Complex(complex) => self.add_complex(*complex),
+        }
+    }
+
+    pub fn merge(&mut self, other: Summation) {
+        self.add_big_int(&other.integer);
+        if let Some(decimal) = other.decimal {
+            self.add_decimal(&decimal);
+        }
+        if let Some(rational) = other.rational {
+            self.add_rational(&rational);
+        }
+        if let Some(inexact) = other.inexact {
+            self.inexact.get_or_insert_default().merge(&inexact);
+        }
+        if let Some(imaginary) = other.imaginary {
+            self.imaginary.get_or_insert_default().merge(&imaginary);
+        }
+    }
+
+    // Returns the sum of the highest kind added, in the order of promotion
+    // `Int` < `BigInt` < `Decimal` < `Rational` < `Float` < `Complex`, where an integer sum is `Int` if it fits in `i32`.
+    pub fn result(&self) -> Value {
+        let exact = match (&self.rational, &self.decimal) {
+            (Some(rational), decimal) => {
+                let sum = rational + &Rational::from(self.integer.clone());
+                Value::Rational(match decimal {
+                    Some(decimal) => &sum + &decimal.to_rational(),
+                    None => sum,
+                })
+            },
+            (None, Some(decimal)) => Value::Decimal(decimal + &Decimal::from(self.integer.clone())),
+            (None, None) => match self.integer.to_i32() {
+                Some(int) => Value::Int(int),
+                None => Value::BigInt(self.integer.clone()),
+            },
+        };
+        let Some(inexact) = self.inexact else {
+            return exact;
+        };
+        let re = exact.to_f64() + inexact.value();
+        match self.imaginary {
+            Some(imaginary) => Value::Complex(Complex::new(re, imaginary.value())),
+            None => Value::Float(re),
+        }
+    }
+}
+
+impl Default for Summation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arith() {
+        assert_eq!(shift_int(i32::MAX, 1.0, Overflow::Checked), Shifted::Overflow);
+        assert_eq!(shift_int(i32::MAX, 1.0, Overflow::Saturating), Shifted::Kept(i32::MAX));
+        assert_eq!(shift_int(i32::MAX, 1.0, Overflow::Wrapping), Shifted::Kept(i32::MIN));
+        assert_eq!(shift_int(i32::MAX, 1.0, Overflow::Promote), Shifted::Promoted(2147483648.0));
+        assert_eq!(shift_big_int(&BigInt::from(1), 0.5, Overflow::Promote), Shifted::Promoted("1.5".parse().unwrap()));
+        assert_eq!(shift_decimal(&Decimal::from(1), f64::NAN), None);
+
+        let seven_halves = Rational::new(BigInt::from(7), BigInt::from(2)).unwrap();
+        assert_eq!(calculate_int(Operator::Add, i32::MAX, 1), Value::BigInt(BigInt::from(2147483648i64)));
+        assert_eq!(calculate_int(Operator::Div, 7, 2), Value::Rational(seven_halves.clone()));
+        assert_eq!(calculate_int(Operator::Div, 1, 0), Value::Float(f64::INFINITY));
+        assert_eq!(negate_int(i32::MIN), Value::BigInt(BigInt::from(2147483648i64)));
+        assert_eq!((negate(Value::Float(1.5)), round(2.5), round(1e10)), (Value::Float(-1.5), Value::Int(3), Value::Float(1e10)));
+
+        assert_eq!(shift(&Value::Int(i32::MAX), 1.0, Overflow::Promote), Shifted::Promoted(Value::Float(2147483648.0)));
+        let mut shifter = Shifter::<Value>::new(1.0, Overflow::Promote);
+        assert_eq!(shifter.shift(Value::Int(i32::MAX)), Value::Float(2147483648.0));
+        assert_eq!(shifter.shift_in_place(Value::Int(i32::MAX)), None);
+        let mut other = shifter.split();
+        assert_eq!(other.shift(Value::Decimal(Decimal::from(1))), Value::Decimal(Decimal::from(2)));
+        assert_eq!(Shifter::<Value>::new(f64::NAN, Overflow::Checked).shift(Value::Int(1)), Value::Int(1));
+        shifter.merge(other);
+        assert_eq!(shifter.errors(), &[OverflowError { number: Value::Int(i32::MAX), step: 1.0 }]);
+
+        let mut summation = Summation::new();
+        summation.add_int(i32::MAX);
+        summation.add_int(1);
+        assert_eq!(summation.result(), Value::BigInt(BigInt::from(2147483648i64)));
+        let mut other = Summation::new();
+        other.add_decimal(&"0.5".parse().unwrap());
+        other.add_rational(&seven_halves);
+        summation.merge(other);
+        assert_eq!(summation.result(), Value::Rational(Rational::from(BigInt::from(2147483652i64))));
+        summation.add_float(0.5);
+        assert_eq!(summation.result(), Value::Float(2147483652.5));
+    }
+}
@@ -0,0 +1,349 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+// An arbitrary-precision integer stored as a sign and a little-endian magnitude of 32-bit digits.
+// The magnitude has no leading zero digits, and zero is never negative.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        BigInt { negative: false, magnitude: Vec::new() }
+    }
+
+    fn from_magnitude(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        let negative = negative && !magnitude.is_empty();
+        BigInt { negative, magnitude }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> Self {
+        BigInt { negative: false, magnitude: self.magnitude.clone() }
+    }
+
+    // Returns the number of bits of the magnitude.
+    pub fn bits(&self) -> u64 {
+        match self.magnitude.last() {
+            Some(digit) => self.magnitude.len() as u64 * 32 - digit.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    pub fn pow(&self, exp: u32) -> Self {
+        (0..exp).fold(BigInt::from(1), |acc, _| &acc * self)
+    }
+
+    // Divides truncating toward zero, so the remainder has the sign of `self`.
+    // Returns `None` if `rhs` is zero.
+    pub fn div_rem(&self, rhs: &BigInt) -> Option<(BigInt, BigInt)> {
+        if rhs.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &rhs.magnitude);
+        Some((
+            BigInt::from_magnitude(self.negative != rhs.negative, quotient),
+            BigInt::from_magnitude(self.negative, remainder),
+        ))
+    }
+
+    pub fn gcd(&self, rhs: &BigInt) -> BigInt {
+        let (mut a, mut b) = (self.abs(), rhs.abs());
+        while !b.is_zero() {
+            let (_, remainder) = a.div_rem(&b).expect("divisor is not zero");
+            a = b;
+            b = remainder;
+        }
+        a
+    }
+
+    pub fn to_i32(&self) -> Option<i32> {
+        i32::try_from(self.to_i64()?).ok()
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let magnitude = self.magnitude.iter().rev().fold(0i128, |acc, &digit| (acc << 32) | digit as i128);
+        i64::try_from(if self.negative { -magnitude } else { magnitude }).ok()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.magnitude.iter().rev().fold(0.0, |acc, &digit| acc * 4294967296.0 + digit as f64);
+        if self.negative { -magnitude } else { magnitude }
+    }
+}
+
+impl From<i32> for BigInt {
+    fn from(n: i32) -> Self {
+        BigInt::from(n as i64)
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(n: i64) -> Self {
+        let magnitude = n.unsigned_abs();
+        BigInt::from_magnitude(n < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        if self.negative == rhs.negative {
+            return BigInt::from_magnitude(self.negative, add_magnitude(&self.magnitude, &rhs.magnitude));
+        }
+        match cmp_magnitude(&self.magnitude, &rhs.magnitude) {
+            Ordering::Less => BigInt::from_magnitude(rhs.negative, sub_magnitude(&rhs.magnitude, &self.magnitude)),
+            _ => BigInt::from_magnitude(self.negative, sub_magnitude(&self.magnitude, &rhs.magnitude)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + &-rhs
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut product = vec![0u32; self.magnitude.len() + rhs.magnitude.len()];
+        for (i, &a) in self.magnitude.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in rhs.magnitude.iter().enumerate() {
+                let digit = product[i + j] as u64 + a as u64 * b as u64 + carry;
+                product[i + j] = digit as u32;
+                carry = digit >> 32;
+            }
+            product[i + rhs.magnitude.len()] = carry as u32;
+        }
+        BigInt::from_magnitude(self.negative != rhs.negative, product)
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> Self::Output {
+        BigInt::from_magnitude(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Add for BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        &self + &rhs
+    }
+}
+
+impl Sub for BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        &self - &rhs
+    }
+}
+
+impl Mul for BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Splits the magnitude into chunks of 9 decimal digits, from the least significant.
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            let (quotient, remainder) = div_rem_digit(&magnitude, 1_000_000_000);
+            chunks.push(remainder);
+            magnitude = quotient;
+        }
+        let mut digits = chunks.pop().unwrap_or(0).to_string();
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{chunk:09}"));
+        }
+        f.pad_integral(!self.negative, "", &digits)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBigIntError;
+
+impl fmt::Display for ParseBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid digit found in string")
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseBigIntError);
+        }
+        let ten = BigInt::from(10);
+        let magnitude = digits.bytes().fold(BigInt::zero(), |acc, b| &(&acc * &ten) + &BigInt::from((b - b'0') as i32));
+        Ok(if negative { -magnitude } else { magnitude })
+    }
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let digit = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        sum.push(digit as u32);
+        carry = digit >> 32;
+    }
+    sum.push(carry as u32);
+    sum
+}
+
+// Requires `a >= b`.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &digit) in a.iter().enumerate() {
+        let mut digit = digit as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = if digit < 0 { 1 } else { 0 };
+        if digit < 0 {
+            digit += 1 << 32;
+        }
+        difference.push(digit as u32);
+    }
+    difference
+}
+
+fn div_rem_digit(a: &[u32], b: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder = 0u64;
+    for (i, &digit) in a.iter().enumerate().rev() {
+        let dividend = (remainder << 32) | digit as u64;
+        quotient[i] = (dividend / b as u64) as u32;
+        remainder = dividend % b as u64;
+    }
+    while quotient.last() == Some(&0) {
+        quotient.pop();
+    }
+    (quotient, remainder as u32)
+}
+
+// Divides by the binary long division, one bit at a time.
+fn div_rem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if b.len() == 1 {
+        let (quotient, remainder) = div_rem_digit(a, b[0]);
+        return (quotient, vec![remainder]);
+    }
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for i in (0..a.len() * 32).rev() {
+        let mut carry = (a[i / 32] >> (i % 32)) & 1;
+        for digit in remainder.iter_mut() {
+            let shifted = (*digit << 1) | carry;
+            carry = *digit >> 31;
+            *digit = shifted;
+        }
+        if carry != 0 {
+            remainder.push(carry);
+        }
+        if cmp_magnitude(&remainder, b) != Ordering::Less {
+            remainder = sub_magnitude(&remainder, b);
+            while remainder.last() == Some(&0) {
+                remainder.pop();
+            }
+            quotient[i / 32] |= 1 << (i % 32);
+        }
+    }
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn big_int() {
+        let a: BigInt = "123456789012345678901234567890".parse().unwrap();
+        let b: BigInt = "-987654321098765432109876543210".parse().unwrap();
+        assert_eq!((&a + &b).to_string(), "-864197532086419753208641975320");
+        assert_eq!((&a - &b).to_string(), "1111111110111111111011111111100");
+        assert_eq!((&a * &b).to_string(), "-121932631137021795226185032733622923332237463801111263526900");
+
+        let (quotient, remainder) = b.div_rem(&a).unwrap();
+        assert_eq!((quotient.to_string(), remainder.to_string()), ("-8".to_string(), "-9000000000900000000090".to_string()));
+        let (quotient, remainder) = a.div_rem(&BigInt::from(-7)).unwrap();
+        assert_eq!(&(&quotient * &BigInt::from(-7)) + &remainder, a);
+        assert_eq!(a.div_rem(&BigInt::zero()), None);
+
+        assert_eq!(BigInt::from(12).gcd(&BigInt::from(-18)), BigInt::from(6));
+        assert_eq!(BigInt::from(2).pow(64).to_string(), "18446744073709551616");
+        assert_eq!((BigInt::from(2).pow(64).bits(), BigInt::from(-1).bits(), BigInt::zero().bits()), (65, 1, 0));
+        assert_eq!(BigInt::from(i64::MIN).to_i64(), Some(i64::MIN));
+        assert_eq!(BigInt::from(i32::MAX).to_i32(), Some(i32::MAX));
+        assert_eq!(a.to_i64(), None);
+        assert_eq!(BigInt::from(-3).to_f64(), -3.0);
+        assert!(b < a && BigInt::from(-1) < BigInt::zero());
+        assert_eq!(format!("{:>5}", BigInt::from(-42)), "  -42");
+        assert!("12a".parse::<BigInt>().is_err() && "-".parse::<BigInt>().is_err());
+    }
+}
@@ -0,0 +1,226 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+use crate::utils::num::{BigInt, Rational};
+
+// A fixed-point decimal number `mantissa * 10^-scale`.
+// Numbers with the same value but different scales, such as `1.5` and `1.50`, are equal.
+#[derive(Debug, Clone)]
+pub struct Decimal {
+    mantissa: BigInt,
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn new(mantissa: BigInt, scale: u32) -> Self {
+        Decimal { mantissa, scale }
+    }
+
+    pub fn mantissa(&self) -> &BigInt {
+        &self.mantissa
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    // Converts the shortest decimal representation of `f`, so `0.1` becomes exactly `0.1`.
+    // Returns `None` if `f` is not finite.
+    pub fn from_f64(f: f64) -> Option<Self> {
+        if !f.is_finite() {
+            return None;
+        }
+        format!("{f}").parse().ok()
+    }
+
+    pub fn rescale(&self, scale: u32) -> Self {
+        if scale >= self.scale {
+            let mantissa = &self.mantissa * &BigInt::from(10).pow(scale - self.scale);
+            Decimal { mantissa, scale }
+        } else {
+            let (mantissa, _) = self.mantissa.div_rem(&BigInt::from(10).pow(self.scale - scale)).expect("divisor is not zero");
+            Decimal { mantissa, scale }
+        }
+    }
+
+    // Rounds half away from zero to `scale` fraction digits, or pads zeros if `scale` is larger.
+    pub fn round(&self, scale: u32) -> Self {
+        if scale >= self.scale {
+            return self.rescale(scale);
+        }
+        let (mantissa, remainder) = self.rescale(scale + 1).mantissa.div_rem(&BigInt::from(10)).expect("divisor is not zero");
+        let mantissa = match remainder.to_i32() {
+            Some(5..) => &mantissa + &BigInt::from(1),
+            Some(..=-5) => &mantissa - &BigInt::from(1),
+            _ => mantissa,
+        };
+        Decimal { mantissa, scale }
+    }
+
+    // Truncates toward zero.
+    pub fn trunc(&self) -> BigInt {
+        self.rescale(0).mantissa
+    }
+
+    pub fn to_rational(&self) -> Rational {
+        Rational::new(self.mantissa.clone(), BigInt::from(10).pow(self.scale)).expect("denominator is not zero")
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().expect("a decimal is formatted as a valid float")
+    }
+}
+
+impl From<BigInt> for Decimal {
+    fn from(n: BigInt) -> Self {
+        Decimal::new(n, 0)
+    }
+}
+
+impl From<i32> for Decimal {
+    fn from(n: i32) -> Self {
+        Decimal::from(BigInt::from(n))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        self.rescale(scale).mantissa.cmp(&other.rescale(scale).mantissa)
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &Decimal {
+    type Output = Decimal;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let scale = self.scale.max(rhs.scale);
+        Decimal::new(&self.rescale(scale).mantissa + &rhs.rescale(scale).mantissa, scale)
+    }
+}
+
+impl Sub for &Decimal {
+    type Output = Decimal;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + &-rhs
+    }
+}
+
+impl Mul for &Decimal {
+    type Output = Decimal;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Decimal::new(&self.mantissa * &rhs.mantissa, self.scale + rhs.scale)
+    }
+}
+
+impl Neg for &Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Self::Output {
+        Decimal::new(-&self.mantissa, self.scale)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.abs().to_string();
+        let scale = self.scale as usize;
+        let digits = if scale == 0 {
+            digits
+        } else {
+            let digits = format!("{digits:0>width$}", width = scale + 1);
+            let (integer, fraction) = digits.split_at(digits.len() - scale);
+            format!("{integer}.{fraction}")
+        };
+        f.pad_integral(!self.mantissa.is_negative(), "", &digits)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDecimalError;
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid decimal literal")
+    }
+}
+
+// The largest scale, and the largest exponent of a decimal without a fraction, accepted by `from_str`,
+// so that a literal such as `1e2000000000` is rejected rather than expanded into billions of digits.
+// It is large enough for any `f64`, whose shortest representation has fewer than 400 fraction digits.
+const MAX_SCALE: i64 = 4096;
+
+// Parses `[+-]digits[.digits]`, and the scale is the number of fraction digits.
+// A float formatted in exponent notation, such as `1e-7`, is also accepted.
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (s, exponent) = match s.split_once(['e', 'E']) {
+            Some((s, exponent)) => (s, exponent.parse::<i32>().map_err(|_| ParseDecimalError)?),
+            None => (s, 0),
+        };
+        let (sign, s) = match s.strip_prefix('-') {
+            Some(s) => ("-", s),
+            None => ("", s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (integer, fraction) = s.split_once('.').unwrap_or((s, ""));
+        if integer.is_empty() && fraction.is_empty() || !integer.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+            return Err(ParseDecimalError);
+        }
+        let mantissa: BigInt = format!("{sign}0{integer}{fraction}").parse().map_err(|_| ParseDecimalError)?;
+        let scale = fraction.len() as i64 - exponent as i64;
+        if scale.abs() > MAX_SCALE {
+            return Err(ParseDecimalError);
+        }
+        match u32::try_from(scale) {
+            Ok(scale) => Ok(Decimal::new(mantissa, scale)),
+            Err(_) if scale < 0 => Ok(Decimal::new(&mantissa * &BigInt::from(10).pow((-scale) as u32), 0)),
+            Err(_) => Err(ParseDecimalError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal() {
+        let d = |s: &str| s.parse::<Decimal>().unwrap();
+        assert_eq!(d("1.5"), d("1.50"));
+        assert_eq!(&d("0.1") + &d("0.2"), d("0.3"));
+        assert_eq!(&d("1.25") - &d("2"), d("-0.75"));
+        assert_eq!(&d("1.5") * &d("-0.2"), d("-0.3"));
+        assert_eq!(d("-12.345").trunc(), BigInt::from(-12));
+        assert_eq!((d("-12.345").round(2).to_string(), d("0.25").round(1).to_string(), d("1.5").round(3).to_string()), ("-12.35".to_string(), "0.3".to_string(), "1.500".to_string()));
+        assert_eq!(d("0.25").to_rational(), Rational::new(BigInt::from(1), BigInt::from(4)).unwrap());
+        assert!(d("-0.5") < d("0.25"));
+        assert_eq!((d("-0.05").to_string(), d("12.30").to_string(), d("7").to_string()), ("-0.05".to_string(), "12.30".to_string(), "7".to_string()));
+        assert_eq!((d("1e3").to_string(), d("1.5e-3").to_string(), d(".5").to_string()), ("1000".to_string(), "0.0015".to_string(), "0.5".to_string()));
+        assert_eq!(Decimal::from_f64(0.1), Some(d("0.1")));
+        assert_eq!(Decimal::from_f64(f64::NAN), None);
+        assert_eq!(d("-2.5").to_f64(), -2.5);
+        assert!("1.2.3".parse::<Decimal>().is_err() && "-".parse::<Decimal>().is_err() && "1.x".parse::<Decimal>().is_err());
+        assert!("1e2000000000".parse::<Decimal>().is_err() && "1e-999999999".parse::<Decimal>().is_err());
+        assert_eq!((d("1e4096").scale(), d("1e-4096").scale()), (0, 4096));
+        assert_eq!(Decimal::from_f64(f64::MIN_POSITIVE / 2.0).map(|d| d.to_f64()), Some(f64::MIN_POSITIVE / 2.0));
+    }
+}
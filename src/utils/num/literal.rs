@@ -0,0 +1,133 @@
+use std::fmt;
+use std::str::FromStr;
+use super::BigInt;
+
+// A numeric literal, which is either an integer of any size or a float.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Integer(BigInt),
+    Float(f64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLiteralError;
+
+impl fmt::Display for ParseLiteralError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid number literal")
+    }
+}
+
+// Parses `[+-]digits` and `[+-]0x<hex digits>` as integers, and any other form accepted by `f64`,
+// such as `1.5`, `1e-3`, `inf` and `NaN`, as floats. Underscores may separate digits, as in `1_000`.
+impl FromStr for Literal {
+    type Err = ParseLiteralError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if s.starts_with(['+', '-']) {
+            return Err(ParseLiteralError);
+        }
+        let (radix, digits) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(digits) => (16, digits),
+            None => (10, s),
+        };
+        let digits = strip_underscores(digits, radix)?;
+        if !digits.is_empty() && digits.chars().all(|c| c.is_digit(radix)) {
+            let base = BigInt::from(radix as i32);
+            let magnitude = digits.chars().fold(BigInt::zero(), |acc, c| {
+                &(&acc * &base) + &BigInt::from(c.to_digit(radix).expect("a digit of the radix") as i32)
+            });
+            return Ok(Literal::Integer(if negative { -magnitude } else { magnitude }));
+        }
+        if radix == 16 {
+            return Err(ParseLiteralError);
+        }
+        let float: f64 = digits.parse().map_err(|_| ParseLiteralError)?;
+        Ok(Literal::Float(if negative { -float } else { float }))
+    }
+}
+
+// Removes the underscores, each of which must be between two digits of the radix.
+fn strip_underscores(s: &str, radix: u32) -> Result<String, ParseLiteralError> {
+    for (i, _) in s.match_indices('_') {
+        let before = s[..i].chars().next_back();
+        let after = s[i + 1..].chars().next();
+        if !before.is_some_and(|c| c.is_digit(radix)) || !after.is_some_and(|c| c.is_digit(radix)) {
+            return Err(ParseLiteralError);
+        }
+    }
+    Ok(s.replace('_', ""))
+}
+
+// Reports the first item of a list which cannot be parsed.
+// `index` counts the items from 0, and `offset` is the byte offset of the item in the list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseListError<E> {
+    pub index: usize,
+    pub offset: usize,
+    pub item: String,
+    pub error: E,
+}
+
+impl<E: fmt::Display> fmt::Display for ParseListError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "item {} `{}` at offset {}: {}", self.index, self.item, self.offset, self.error)
+    }
+}
+
+// Parses a list of items separated by commas and/or whitespace, such as `1, 2.5 0x10`.
+// A blank list has no items, but an empty item between commas, as in `1,,2`, is parsed and fails.
+pub fn parse_list<T: FromStr>(s: &str) -> Result<Vec<T>, ParseListError<T::Err>> {
+    let mut items = Vec::new();
+    if s.trim().is_empty() {
+        return Ok(items);
+    }
+    for segment in s.split(',') {
+        let mut tokens: Vec<&str> = segment.split_whitespace().collect();
+        if tokens.is_empty() {
+            tokens.push(segment.trim());
+        }
+        for token in tokens {
+            let offset = token.as_ptr() as usize - s.as_ptr() as usize;
+            match token.parse() {
+                Ok(item) => items.push(item),
+                Err(error) => return Err(ParseListError { index: items.len(), offset, item: token.to_string(), error }),
+            }
+        }
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal() {
+        let int = |n: i64| Ok(Literal::Integer(BigInt::from(n)));
+        assert_eq!("42".parse(), int(42));
+        assert_eq!("-1_000".parse(), int(-1000));
+        assert_eq!("0xFF".parse(), int(255));
+        assert_eq!("-0x7fff_ffff".parse(), int(-0x7fff_ffff));
+        assert_eq!("99999999999999999999".parse(), Ok(Literal::Integer("99999999999999999999".parse().unwrap())));
+        assert_eq!("1.5".parse(), Ok(Literal::Float(1.5)));
+        assert_eq!("-2.5e-3".parse(), Ok(Literal::Float(-0.0025)));
+        assert_eq!("1_0e1_0".parse(), Ok(Literal::Float(1e11)));
+        assert_eq!("inf".parse(), Ok(Literal::Float(f64::INFINITY)));
+        for s in ["", "-", "--1", "+-1", "_1", "1_", "1__0", "1_.5", "0x", "0x_1", "0x1.5", "0xG", "1.2.3", "abc"] {
+            assert_eq!(s.parse::<Literal>(), Err(ParseLiteralError), "{s}");
+        }
+
+        assert_eq!(parse_list::<i32>(" 1, 2 3,4 "), Ok(vec![1, 2, 3, 4]));
+        assert_eq!(parse_list::<i32>("  "), Ok(vec![]));
+        let error = parse_list::<Literal>("1, 2 x3").unwrap_err();
+        assert_eq!((error.index, error.offset, error.item.as_str()), (2, 5, "x3"));
+        assert_eq!(error.to_string(), "item 2 `x3` at offset 5: invalid number literal");
+        let error = parse_list::<Literal>("1,,2").unwrap_err();
+        assert_eq!((error.index, error.offset, error.item.as_str()), (1, 2, ""));
+    }
+}
@@ -0,0 +1,12 @@
+pub mod big_int;
+pub mod rational;
+pub mod decimal;
+pub mod complex;
+pub mod literal;
+pub mod arith;
+
+pub use big_int::BigInt;
+pub use rational::Rational;
+pub use decimal::Decimal;
+pub use complex::Complex;
+pub use literal::Literal;
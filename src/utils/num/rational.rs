@@ -0,0 +1,152 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use crate::utils::num::BigInt;
+
+// An exact fraction kept in the lowest terms with a positive denominator.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: BigInt,
+    denom: BigInt,
+}
+
+impl Rational {
+    // Returns `None` if `denom` is zero.
+    pub fn new(numer: BigInt, denom: BigInt) -> Option<Self> {
+        if denom.is_zero() {
+            return None;
+        }
+        let gcd = numer.gcd(&denom);
+        let (mut numer, _) = numer.div_rem(&gcd)?;
+        let (mut denom, _) = denom.div_rem(&gcd)?;
+        if denom.is_negative() {
+            numer = -numer;
+            denom = -denom;
+        }
+        Some(Rational { numer, denom })
+    }
+
+    pub fn numer(&self) -> &BigInt {
+        &self.numer
+    }
+
+    pub fn denom(&self) -> &BigInt {
+        &self.denom
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denom == BigInt::from(1)
+    }
+
+    // Truncates toward zero.
+    pub fn trunc(&self) -> BigInt {
+        self.numer.div_rem(&self.denom).expect("denominator is not zero").0
+    }
+
+    // Returns `None` if `rhs` is zero.
+    pub fn checked_div(&self, rhs: &Rational) -> Option<Rational> {
+        Rational::new(&self.numer * &rhs.denom, &self.denom * &rhs.numer)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        // Shifts both terms right first, so that large terms do not overflow to infinity.
+        let shift = self.numer.bits().max(self.denom.bits()).saturating_sub(1000);
+        if shift == 0 {
+            return self.numer.to_f64() / self.denom.to_f64();
+        }
+        let divisor = BigInt::from(2).pow(shift as u32);
+        let (numer, _) = self.numer.div_rem(&divisor).expect("divisor is not zero");
+        let (denom, _) = self.denom.div_rem(&divisor).expect("divisor is not zero");
+        numer.to_f64() / denom.to_f64()
+    }
+}
+
+impl From<BigInt> for Rational {
+    fn from(n: BigInt) -> Self {
+        Rational { numer: n, denom: BigInt::from(1) }
+    }
+}
+
+impl From<i32> for Rational {
+    fn from(n: i32) -> Self {
+        Rational::from(BigInt::from(n))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.numer * &other.denom).cmp(&(&other.numer * &self.denom))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &Rational {
+    type Output = Rational;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let numer = &(&self.numer * &rhs.denom) + &(&rhs.numer * &self.denom);
+        Rational::new(numer, &self.denom * &rhs.denom).expect("denominator is not zero")
+    }
+}
+
+impl Sub for &Rational {
+    type Output = Rational;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + &-rhs
+    }
+}
+
+impl Mul for &Rational {
+    type Output = Rational;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Rational::new(&self.numer * &rhs.numer, &self.denom * &rhs.denom).expect("denominator is not zero")
+    }
+}
+
+impl Neg for &Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Self::Output {
+        Rational { numer: -&self.numer, denom: self.denom.clone() }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rational() {
+        let r = |n: i32, d: i32| Rational::new(BigInt::from(n), BigInt::from(d)).unwrap();
+        assert_eq!(r(2, -4), r(-1, 2));
+        assert_eq!(Rational::new(BigInt::from(1), BigInt::zero()), None);
+        assert_eq!(&r(1, 2) + &r(1, 3), r(5, 6));
+        assert_eq!(&r(1, 2) - &r(1, 3), r(1, 6));
+        assert_eq!(&r(2, 3) * &r(3, 4), r(1, 2));
+        assert_eq!(r(1, 2).checked_div(&r(1, 4)), Some(r(2, 1)));
+        assert_eq!(r(1, 2).checked_div(&r(0, 1)), None);
+        assert_eq!(r(-7, 2).trunc(), BigInt::from(-3));
+        assert!(r(1, 3) < r(1, 2) && r(-1, 2) < r(-1, 3));
+        assert_eq!(r(3, 4).to_f64(), 0.75);
+        let huge = BigInt::from(3).pow(2000);
+        assert_eq!(Rational::new(&huge * &BigInt::from(2), huge).unwrap().to_f64(), 2.0);
+        assert_eq!((r(6, 3).to_string(), r(-1, 3).to_string()), ("2".to_string(), "-1/3".to_string()));
+    }
+}
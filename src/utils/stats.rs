@@ -0,0 +1,486 @@
+use std::sync::{Arc, OnceLock};
+use crate::utils::num::arith::{Summation, Value};
+
+// Running mean and variance by Welford's algorithm,
+// which avoids the catastrophic cancellation of the naive sum of squares.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Welford {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl Welford {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    // Combines the statistics of two disjoint sets of values by Chan's algorithm.
+    pub fn merge(&mut self, other: &Welford) {
+        if other.count == 0 {
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * self.count as f64 * other.count as f64 / count as f64;
+        self.count = count;
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    // The population variance, which divides by `n`.
+    pub fn variance(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.m2 / self.count as f64)
+    }
+
+    // The sample variance, which divides by `n - 1`.
+    pub fn sample_variance(&self) -> Option<f64> {
+        (self.count > 1).then(|| self.m2 / (self.count - 1) as f64)
+    }
+
+    pub fn stddev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    pub fn sample_stddev(&self) -> Option<f64> {
+        self.sample_variance().map(f64::sqrt)
+    }
+}
+
+// Sums floats by Neumaier's compensated summation, which keeps the rounding error of each addition.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, value: f64) {
+        let sum = self.sum + value;
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - sum) + value;
+        } else {
+            self.compensation += (value - sum) + self.sum;
+        }
+        self.sum = sum;
+    }
+
+    pub fn value(&self) -> f64 {
+        self.sum + self.compensation
+    }
+
+    pub fn merge(&mut self, other: &CompensatedSum) {
+        self.push(other.sum);
+        self.compensation += other.compensation;
+    }
+}
+
+// Counts values in equal-width bins over `[lo, hi)`.
+// Values below `lo` or from `hi` are counted as underflow or overflow, and `NaN` is not counted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    lo: f64,
+    hi: f64,
+    counts: Vec<usize>,
+    underflow: usize,
+    overflow: usize,
+}
+
+impl Histogram {
+    pub fn new(lo: f64, hi: f64, bins: usize) -> Self {
+        assert!(lo < hi && bins > 0, "a histogram needs a non-empty range and at least one bin");
+        Histogram {
+            lo,
+            hi,
+            counts: vec![0; bins],
+            underflow: 0,
+            overflow: 0,
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        if value < self.lo {
+            self.underflow += 1;
+        } else if value >= self.hi {
+            self.overflow += 1;
+        } else if !value.is_nan() {
+            let bins = self.counts.len();
+            let bin = ((value - self.lo) / (self.hi - self.lo) * bins as f64) as usize;
+            self.counts[bin.min(bins - 1)] += 1;
+        }
+    }
+
+    // Adds the counts of a histogram with the same bins.
+    pub fn merge(&mut self, other: &Histogram) {
+        assert!(self.lo == other.lo && self.hi == other.hi && self.counts.len() == other.counts.len(), "histograms to merge must have the same bins");
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        self.underflow += other.underflow;
+        self.overflow += other.overflow;
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.fill(0);
+        self.underflow = 0;
+        self.overflow = 0;
+    }
+
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    pub fn underflow(&self) -> usize {
+        self.underflow
+    }
+
+    pub fn overflow(&self) -> usize {
+        self.overflow
+    }
+
+    // Returns the `bins + 1` edges of the bins, from `lo` to `hi`.
+    pub fn edges(&self) -> Vec<f64> {
+        let width = (self.hi - self.lo) / self.counts.len() as f64;
+        (0..=self.counts.len()).map(|i| if i == self.counts.len() { self.hi } else { self.lo + width * i as f64 }).collect()
+    }
+}
+
+// Returns the `q`-quantile of sorted values, interpolating linearly between the closest ranks.
+// Returns `None` if there are no values or `q` is not within `[0, 1]`.
+pub fn quantile(sorted: &[f64], q: f64) -> Option<f64> {
+    if sorted.is_empty() || !(0.0..=1.0).contains(&q) {
+        return None;
+    }
+    let rank = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
+// Computes count, sum, min, max, mean and variance of numbers in one pass,
+// and optionally quantiles and a histogram. The statistics of the visitor examples visit their numbers into it.
+// The sum is exact for the exact kinds. The min and max are compared in the representation `N` of the caller,
+// and the other statistics are computed on `Value::to_f64`, so the numbers without a real value,
+// `NaN` and `Complex` which is not real, are counted and summed only.
+#[derive(Debug, Clone)]
+pub struct Summary<N> {
+    count: usize,
+    sum: Summation,
+    min: Option<N>,
+    max: Option<N>,
+    welford: Welford,
+    values: Option<Vec<f64>>,
+    sorted: OnceLock<Vec<f64>>,
+    histogram: Option<Histogram>,
+}
+
+impl<N: PartialOrd> Summary<N> {
+    pub fn new() -> Self {
+        Summary {
+            count: 0,
+            sum: Summation::new(),
+            min: None,
+            max: None,
+            welford: Welford::new(),
+            values: None,
+            sorted: OnceLock::new(),
+            histogram: None,
+        }
+    }
+
+    // Keeps the values to compute the median and quantiles.
+    pub fn with_quantiles(mut self) -> Self {
+        self.values = Some(Vec::new());
+        self
+    }
+
+    pub fn with_histogram(mut self, lo: f64, hi: f64, bins: usize) -> Self {
+        self.histogram = Some(Histogram::new(lo, hi, bins));
+        self
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn sum(&self) -> Value {
+        self.sum.result()
+    }
+
+    pub fn min(&self) -> Option<&N> {
+        self.min.as_ref()
+    }
+
+    pub fn max(&self) -> Option<&N> {
+        self.max.as_ref()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        self.welford.mean()
+    }
+
+    pub fn variance(&self) -> Option<f64> {
+        self.welford.variance()
+    }
+
+    pub fn sample_variance(&self) -> Option<f64> {
+        self.welford.sample_variance()
+    }
+
+    pub fn stddev(&self) -> Option<f64> {
+        self.welford.stddev()
+    }
+
+    pub fn sample_stddev(&self) -> Option<f64> {
+        self.welford.sample_stddev()
+    }
+
+    // Returns `None` if there are no values or the quantiles are not enabled by `with_quantiles`.
+    pub fn median(&self) -> Option<f64> {
+        self.quantile(0.5)
+    }
+
+    // NOTE: The values are sorted once for all the quantiles, and sorted again only after
+    //       `push` or `merge` adds values.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let values = self.values.as_ref()?;
+        let sorted = self.sorted.get_or_init(|| {
+            let mut sorted = values.clone();
+            sorted.sort_by(f64::total_cmp);
+            sorted
+        });
+        quantile(sorted, q)
+    }
+
+    pub fn histogram(&self) -> Option<&Histogram> {
+        self.histogram.as_ref()
+    }
+
+    // Adds a number by its value, where `to_number` makes its representation to compare with the min and max.
+    // NOTE: `to_number` is called only for a number with a real value, and twice only for the first one,
+    //       which is both the min and the max.
+    pub fn push(&mut self, value: Value, to_number: impl Fn() -> N) {
+        self.count += 1;
+        self.sum.add(&value);
+        let value = value.to_f64();
+        if value.is_nan() {
+            return;
+        }
+        self.welford.push(value);
+        if let Some(values) = &mut self.values {
+            values.push(value);
+            self.sorted.take();
+        }
+        if let Some(histogram) = &mut self.histogram {
+            histogram.push(value);
+        }
+        let number = to_number();
+        let is_max = self.max.as_ref().is_none_or(|max| number > *max);
+        if self.min.as_ref().is_none_or(|min| number < *min) {
+            if is_max {
+                self.max = Some(to_number());
+            }
+            self.min = Some(number);
+        } else if is_max {
+            self.max = Some(number);
+        }
+    }
+
+    // Returns an empty summary which computes the same statistics.
+    pub fn split(&self) -> Self {
+        let mut histogram = self.histogram.clone();
+        if let Some(histogram) = &mut histogram {
+            histogram.clear();
+        }
+        Summary {
+            values: self.values.as_ref().map(|_| Vec::new()),
+            histogram,
+            ..Summary::new()
+        }
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.count += other.count;
+        self.sum.merge(other.sum);
+        self.welford.merge(&other.welford);
+        if let (Some(values), Some(other)) = (&mut self.values, other.values) {
+            values.extend(other);
+            self.sorted.take();
+        }
+        if let (Some(histogram), Some(other)) = (&mut self.histogram, &other.histogram) {
+            histogram.merge(other);
+        }
+        if let Some(min) = other.min.filter(|min| self.min.as_ref().is_none_or(|self_min| min < self_min)) {
+            self.min = Some(min);
+        }
+        if let Some(max) = other.max.filter(|max| self.max.as_ref().is_none_or(|self_max| max > self_max)) {
+            self.max = Some(max);
+        }
+    }
+}
+
+impl<N: PartialOrd> Default for Summary<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type Op<'a, T, N> = Box<dyn Fn(&mut T, &N) + 'a>;
+type SharedOp<'a, T, N> = Arc<dyn Fn(&mut T, &N) + Send + Sync + 'a>;
+type MergeOp<'a, T> = Arc<dyn Fn(&mut T, T) + Send + Sync + 'a>;
+
+// Folds numbers of the representation `N` into an accumulator by an operation.
+// The accumulators of the visitor examples visit their numbers into it.
+pub struct Fold<'a, T, N: ?Sized> {
+    acc: T,
+    op: Op<'a, T, N>,
+}
+
+impl<'a, T, N: ?Sized> Fold<'a, T, N> {
+    pub fn new<F>(init: T, op: F) -> Self
+    where
+        F: Fn(&mut T, &N) + 'a,
+    {
+        Fold {
+            acc: init,
+            op: Box::new(op),
+        }
+    }
+
+    pub fn push(&mut self, number: &N) {
+        (self.op)(&mut self.acc, number);
+    }
+
+    pub fn result(&self) -> &T {
+        &self.acc
+    }
+}
+
+// A `Fold` which can be split and merged.
+// Each split starts from `identity`, and `merge` combines the result of a split into the result of the splits before it.
+// NOTE: The operations are shared with the threads, so they must be `Send` and `Sync`,
+//       which `Fold` does not require.
+pub struct ParFold<'a, T, N: ?Sized> {
+    acc: T,
+    identity: T,
+    op: SharedOp<'a, T, N>,
+    merge: MergeOp<'a, T>,
+}
+
+impl<'a, T: Clone, N: ?Sized> ParFold<'a, T, N> {
+    pub fn new<F, M>(identity: T, op: F, merge: M) -> Self
+    where
+        F: Fn(&mut T, &N) + Send + Sync + 'a,
+        M: Fn(&mut T, T) + Send + Sync + 'a,
+    {
+        ParFold {
+            acc: identity.clone(),
+            identity,
+            op: Arc::new(op),
+            merge: Arc::new(merge),
+        }
+    }
+
+    pub fn split(&self) -> Self {
+        ParFold {
+            acc: self.identity.clone(),
+            identity: self.identity.clone(),
+            op: Arc::clone(&self.op),
+            merge: Arc::clone(&self.merge),
+        }
+    }
+}
+
+impl<T, N: ?Sized> ParFold<'_, T, N> {
+    pub fn push(&mut self, number: &N) {
+        (self.op)(&mut self.acc, number);
+    }
+
+    pub fn result(&self) -> &T {
+        &self.acc
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        (self.merge)(&mut self.acc, other.acc);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        let values = [1e9 + 4.0, 1e9 + 7.0, 1e9 + 13.0, 1e9 + 16.0];
+        let mut welford = Welford::new();
+        values.iter().for_each(|&v| welford.push(v));
+        assert_eq!((welford.count(), welford.mean(), welford.variance(), welford.sample_variance()), (4, Some(1e9 + 10.0), Some(22.5), Some(30.0)));
+
+        let (mut left, mut right) = (Welford::new(), Welford::new());
+        values[..1].iter().for_each(|&v| left.push(v));
+        values[1..].iter().for_each(|&v| right.push(v));
+        left.merge(&right);
+        assert_eq!((left.mean(), left.variance()), (welford.mean(), welford.variance()));
+        assert_eq!((Welford::new().mean(), left.sample_stddev()), (None, Some(30f64.sqrt())));
+
+        let mut sum = CompensatedSum::new();
+        [1.0, 1e100, 1.0, -1e100].iter().for_each(|&v| sum.push(v));
+        assert_eq!(sum.value(), 2.0);
+        let mut other = CompensatedSum::new();
+        [1e100, 1.0, -1e100].iter().for_each(|&v| other.push(v));
+        sum.merge(&other);
+        assert_eq!(sum.value(), 3.0);
+
+        let mut histogram = Histogram::new(0.0, 10.0, 5);
+        [-1.0, 0.0, 1.9, 2.0, 9.99, 10.0, f64::NAN].iter().for_each(|&v| histogram.push(v));
+        assert_eq!((histogram.counts(), histogram.underflow(), histogram.overflow()), (&[2, 1, 0, 0, 1][..], 1, 1));
+        assert_eq!(histogram.edges(), vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+        let mut other = histogram.clone();
+        other.clear();
+        other.push(5.0);
+        histogram.merge(&other);
+        assert_eq!((histogram.counts(), histogram.underflow()), (&[2, 1, 1, 0, 1][..], 1));
+
+        let mut summary = Summary::new().with_quantiles();
+        for value in [3, 1, 2] {
+            summary.push(Value::Int(value), || value);
+        }
+        let mut other = summary.split();
+        other.push(Value::Float(f64::NAN), || unreachable!("a number without a real value is not compared"));
+        other.push(Value::Int(7), || 7);
+        summary.merge(other);
+        assert_eq!((summary.count(), summary.min(), summary.max()), (5, Some(&1), Some(&7)));
+        assert!(summary.sum().to_f64().is_nan());
+        assert_eq!((summary.mean(), summary.median()), (Some(3.25), Some(2.5)));
+        summary.push(Value::Int(0), || 0);
+        assert_eq!((summary.median(), summary.quantile(1.0)), (Some(2.0), Some(7.0)));
+
+        let mut fold = ParFold::new(0, |acc, number: &i32| *acc += number, |acc, other| *acc += other);
+        let mut split = fold.split();
+        [1, 2].iter().for_each(|number| fold.push(number));
+        split.push(&3);
+        fold.merge(split);
+        assert_eq!(*fold.result(), 6);
+
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!((quantile(&sorted, 0.5), quantile(&sorted, 0.0), quantile(&sorted, 1.0)), (Some(2.5), Some(1.0), Some(4.0)));
+        assert_eq!((quantile(&sorted, 0.25), quantile(&[], 0.5), quantile(&sorted, 1.5)), (Some(1.75), None, None));
+    }
+}
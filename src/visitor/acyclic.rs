@@ -0,0 +1,200 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use crate::visitor::{CompositeElement, ElementA, ElementB, Visit, Visitor};
+
+type Handler<'a, S> = Box<dyn FnMut(&mut S, &mut dyn Any) + 'a>;
+type RefHandler<'a, S> = Box<dyn FnMut(&mut S, &dyn Any) + 'a>;
+
+// NOTE: `AcyclicVisitor` dispatches on the concrete type of an element through `Any`,
+//       so it only handles the element kinds registered by `on`, and any other kind,
+//       including one added by a downstream crate, falls back to the handler registered by `fallback`.
+//       As a `Visit`, it calls the read-only handlers registered by `on_ref` and `fallback_ref` instead.
+pub struct AcyclicVisitor<'a, S> {
+    state: S,
+    handlers: HashMap<TypeId, Handler<'a, S>>,
+    fallback: Option<Handler<'a, S>>,
+    ref_handlers: HashMap<TypeId, RefHandler<'a, S>>,
+    ref_fallback: Option<RefHandler<'a, S>>,
+}
+
+impl<'a, S> AcyclicVisitor<'a, S> {
+    pub fn new(state: S) -> Self {
+        AcyclicVisitor {
+            state,
+            handlers: HashMap::new(),
+            fallback: None,
+            ref_handlers: HashMap::new(),
+            ref_fallback: None,
+        }
+    }
+
+    pub fn on<E, F>(mut self, mut handler: F) -> Self
+    where
+        E: Any,
+        F: FnMut(&mut S, &mut E) + 'a,
+    {
+        self.handlers.insert(TypeId::of::<E>(), Box::new(move |state, element| {
+            if let Some(element) = element.downcast_mut::<E>() {
+                handler(state, element);
+            }
+        }));
+        self
+    }
+
+    pub fn fallback<F>(mut self, handler: F) -> Self
+    where
+        F: FnMut(&mut S, &mut dyn Any) + 'a,
+    {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    pub fn on_ref<E, F>(mut self, mut handler: F) -> Self
+    where
+        E: Any,
+        F: FnMut(&mut S, &E) + 'a,
+    {
+        self.ref_handlers.insert(TypeId::of::<E>(), Box::new(move |state, element| {
+            if let Some(element) = element.downcast_ref::<E>() {
+                handler(state, element);
+            }
+        }));
+        self
+    }
+
+    pub fn fallback_ref<F>(mut self, handler: F) -> Self
+    where
+        F: FnMut(&mut S, &dyn Any) + 'a,
+    {
+        self.ref_fallback = Some(Box::new(handler));
+        self
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn into_state(self) -> S {
+        self.state
+    }
+
+    pub fn visit(&mut self, element: &mut dyn Any) {
+        let handler = match self.handlers.get_mut(&(*element).type_id()) {
+            Some(handler) => handler,
+            None => match self.fallback.as_mut() {
+                Some(fallback) => fallback,
+                None => return,
+            },
+        };
+        handler(&mut self.state, element);
+    }
+
+    pub fn visit_ref(&mut self, element: &dyn Any) {
+        let handler = match self.ref_handlers.get_mut(&element.type_id()) {
+            Some(handler) => handler,
+            None => match self.ref_fallback.as_mut() {
+                Some(fallback) => fallback,
+                None => return,
+            },
+        };
+        handler(&mut self.state, element);
+    }
+}
+
+impl<'a, S> Visitor for AcyclicVisitor<'a, S> {
+    fn visit_element_a(&mut self, element: &mut ElementA) {
+        self.visit(element);
+    }
+
+    fn visit_element_b(&mut self, element: &mut ElementB) {
+        self.visit(element);
+    }
+
+    fn visit_composite_element(&mut self, element: &mut CompositeElement) {
+        self.visit(element);
+    }
+
+    fn visit_other(&mut self, element: &mut dyn Any) {
+        self.visit(element);
+    }
+}
+
+impl<'a, S> Visit for AcyclicVisitor<'a, S> {
+    fn visit_element_a(&mut self, element: &ElementA) {
+        self.visit_ref(element);
+    }
+
+    fn visit_element_b(&mut self, element: &ElementB) {
+        self.visit_ref(element);
+    }
+
+    fn visit_composite_element(&mut self, element: &CompositeElement) {
+        self.visit_ref(element);
+    }
+
+    fn visit_other(&mut self, element: &dyn Any) {
+        self.visit_ref(element);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visitor::{Control, Element};
+
+    // An element kind which `Visitor` does not know, as if added by a downstream crate.
+    struct ElementC(u32);
+
+    impl Element for ElementC {
+        fn accept_at(&mut self, visitor: &mut dyn Visitor, _depth: usize) -> Control {
+            visitor.visit_other(self);
+            Control::Continue
+        }
+
+        fn accept_ref_at(&self, visitor: &mut dyn Visit, _depth: usize) -> Control {
+            visitor.visit_other(self);
+            Control::Continue
+        }
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Counts {
+        a: usize,
+        c: u32,
+        unhandled: usize,
+    }
+
+    #[test]
+    fn acyclic_visitor() {
+        let mut elements: Vec<Box<dyn Element>> = vec![
+            Box::new(ElementA::default()),
+            Box::new(ElementB::default()),
+            Box::new(ElementC(2)),
+            Box::new(CompositeElement::default().with_child(ElementA::default()).with_child(ElementC(3))),
+        ];
+
+        let mut v = AcyclicVisitor::new(Counts::default())
+            .on(|counts: &mut Counts, _: &mut ElementA| counts.a += 1)
+            .on(|counts: &mut Counts, c: &mut ElementC| counts.c += c.0)
+            .fallback(|counts, _| counts.unhandled += 1);
+        for e in elements.iter_mut() {
+            e.accept(&mut v);
+        }
+        assert_eq!(v.into_state(), Counts { a: 2, c: 5, unhandled: 2 });
+
+        let mut v = AcyclicVisitor::new(0).on(|n: &mut u32, c: &mut ElementC| *n += c.0);
+        for e in elements.iter_mut() {
+            e.accept(&mut v);
+        }
+        assert_eq!(v.state(), &5);
+
+        let mut v = AcyclicVisitor::new(Counts::default())
+            .on_ref(|counts: &mut Counts, c: &ElementC| counts.c += c.0)
+            .fallback_ref(|counts, _| counts.unhandled += 1)
+            .on(|counts: &mut Counts, _: &mut ElementC| counts.a += 1);
+        for e in elements.iter() {
+            e.accept_ref(&mut v);
+        }
+        assert_eq!(v.into_state(), Counts { a: 0, c: 5, unhandled: 4 });
+    }
+}
@@ -0,0 +1,284 @@
+use std::any::Any;
+use std::fmt;
+use crate::visitor::{exam2, exam3};
+
+// `exam2` and `exam3` model the same kinds of numbers, as trait objects and as the variants of an enum,
+// so a number of those kinds converts between them losslessly.
+impl From<exam3::Number> for Box<dyn exam2::Number> {
+    fn from(number: exam3::Number) -> Self {
+        use exam3::Number::*;
+        match number {
+            Int(int) => Box::new(exam2::Int(int.0)),
+            Float(float) => Box::new(exam2::Float(float.0)),
+            BigInt(big_int) => Box::new(exam2::BigInt(big_int.0)),
+            Rational(rational) => Box::new(exam2::Rational(rational.0)),
+            Decimal(decimal) => Box::new(exam2::Decimal(decimal.0)),
+            Complex(complex) => Box::new(exam2::Complex(complex.0)),
+        }
+    }
+}
+
+// NOTE: Any type may implement `exam2::Number`, so the kinds of `exam2` are identified by their concrete types.
+//       A foreign implementation fails to convert, even if it accepts a visitor as one of those kinds.
+impl TryFrom<Box<dyn exam2::Number>> for exam3::Number {
+    type Error = UnknownNumberError;
+
+    fn try_from(number: Box<dyn exam2::Number>) -> Result<Self, Self::Error> {
+        let any: &dyn Any = &*number;
+        let converted = if let Some(int) = any.downcast_ref::<exam2::Int>() {
+            exam3::Number::Int(exam3::Int(int.0))
+        } else if let Some(float) = any.downcast_ref::<exam2::Float>() {
+            exam3::Number::Float(exam3::Float(float.0))
+        } else if let Some(big_int) = any.downcast_ref::<exam2::BigInt>() {
+            exam3::Number::BigInt(exam3::BigInt(big_int.0.clone()))
+        } else if let Some(rational) = any.downcast_ref::<exam2::Rational>() {
+            exam3::Number::Rational(exam3::Rational(rational.0.clone()))
+        } else if let Some(decimal) = any.downcast_ref::<exam2::Decimal>() {
+            exam3::Number::Decimal(exam3::Decimal(decimal.0.clone()))
+        } else if let Some(complex) = any.downcast_ref::<exam2::Complex>() {
+            exam3::Number::Complex(exam3::Complex(complex.0))
+        } else {
+            return Err(UnknownNumberError(number));
+        };
+        Ok(converted)
+    }
+}
+
+// Returns the number which failed to convert.
+#[derive(Debug)]
+pub struct UnknownNumberError(pub Box<dyn exam2::Number>);
+
+impl fmt::Display for UnknownNumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown kind of number: {:?}", self.0)
+    }
+}
+
+// Runs an `exam2` visitor over `exam3` numbers, such as `number.accept(&mut Exam2Adapter::new(&mut visitor))`.
+// Each number is visited as a copy of the `exam2` kind, which is copied back after a `Visitor` changes it.
+pub struct Exam2Adapter<'a, V: ?Sized> {
+    visitor: &'a mut V,
+}
+
+impl<'a, V: ?Sized> Exam2Adapter<'a, V> {
+    pub fn new(visitor: &'a mut V) -> Self {
+        Exam2Adapter { visitor }
+    }
+}
+
+impl<V: exam2::Visitor + ?Sized> exam3::Visitor for Exam2Adapter<'_, V> {
+    fn visit_int(&mut self, int: &mut exam3::Int) {
+        let mut copy = exam2::Int(int.0);
+        self.visitor.visit_int(&mut copy);
+        int.0 = copy.0;
+    }
+
+    fn visit_float(&mut self, float: &mut exam3::Float) {
+        let mut copy = exam2::Float(float.0);
+        self.visitor.visit_float(&mut copy);
+        float.0 = copy.0;
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut exam3::BigInt) {
+        let mut copy = exam2::BigInt(big_int.0.clone());
+        self.visitor.visit_big_int(&mut copy);
+        big_int.0 = copy.0;
+    }
+
+    fn visit_rational(&mut self, rational: &mut exam3::Rational) {
+        let mut copy = exam2::Rational(rational.0.clone());
+        self.visitor.visit_rational(&mut copy);
+        rational.0 = copy.0;
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut exam3::Decimal) {
+        let mut copy = exam2::Decimal(decimal.0.clone());
+        self.visitor.visit_decimal(&mut copy);
+        decimal.0 = copy.0;
+    }
+
+    fn visit_complex(&mut self, complex: &mut exam3::Complex) {
+        let mut copy = exam2::Complex(complex.0);
+        self.visitor.visit_complex(&mut copy);
+        complex.0 = copy.0;
+    }
+}
+
+impl<V: exam2::Visit + ?Sized> exam3::Visit for Exam2Adapter<'_, V> {
+    fn visit_int(&mut self, int: &exam3::Int) {
+        self.visitor.visit_int(&exam2::Int(int.0));
+    }
+
+    fn visit_float(&mut self, float: &exam3::Float) {
+        self.visitor.visit_float(&exam2::Float(float.0));
+    }
+
+    fn visit_big_int(&mut self, big_int: &exam3::BigInt) {
+        self.visitor.visit_big_int(&exam2::BigInt(big_int.0.clone()));
+    }
+
+    fn visit_rational(&mut self, rational: &exam3::Rational) {
+        self.visitor.visit_rational(&exam2::Rational(rational.0.clone()));
+    }
+
+    fn visit_decimal(&mut self, decimal: &exam3::Decimal) {
+        self.visitor.visit_decimal(&exam2::Decimal(decimal.0.clone()));
+    }
+
+    fn visit_complex(&mut self, complex: &exam3::Complex) {
+        self.visitor.visit_complex(&exam2::Complex(complex.0));
+    }
+}
+
+// Runs an `exam3` visitor over `exam2` numbers, such as `number.accept(&mut Exam3Adapter::new(&mut visitor))`.
+// Like `Exam2Adapter`, it visits copies and copies back the numbers changed by a `Visitor`.
+pub struct Exam3Adapter<'a, V: ?Sized> {
+    visitor: &'a mut V,
+}
+
+impl<'a, V: ?Sized> Exam3Adapter<'a, V> {
+    pub fn new(visitor: &'a mut V) -> Self {
+        Exam3Adapter { visitor }
+    }
+}
+
+impl<V: exam3::Visitor + ?Sized> exam2::Visitor for Exam3Adapter<'_, V> {
+    fn visit_int(&mut self, int: &mut exam2::Int) {
+        let mut copy = exam3::Int(int.0);
+        self.visitor.visit_int(&mut copy);
+        int.0 = copy.0;
+    }
+
+    fn visit_float(&mut self, float: &mut exam2::Float) {
+        let mut copy = exam3::Float(float.0);
+        self.visitor.visit_float(&mut copy);
+        float.0 = copy.0;
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut exam2::BigInt) {
+        let mut copy = exam3::BigInt(big_int.0.clone());
+        self.visitor.visit_big_int(&mut copy);
+        big_int.0 = copy.0;
+    }
+
+    fn visit_rational(&mut self, rational: &mut exam2::Rational) {
+        let mut copy = exam3::Rational(rational.0.clone());
+        self.visitor.visit_rational(&mut copy);
+        rational.0 = copy.0;
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut exam2::Decimal) {
+        let mut copy = exam3::Decimal(decimal.0.clone());
+        self.visitor.visit_decimal(&mut copy);
+        decimal.0 = copy.0;
+    }
+
+    fn visit_complex(&mut self, complex: &mut exam2::Complex) {
+        let mut copy = exam3::Complex(complex.0);
+        self.visitor.visit_complex(&mut copy);
+        complex.0 = copy.0;
+    }
+}
+
+impl<V: exam3::Visit + ?Sized> exam2::Visit for Exam3Adapter<'_, V> {
+    fn visit_int(&mut self, int: &exam2::Int) {
+        self.visitor.visit_int(&exam3::Int(int.0));
+    }
+
+    fn visit_float(&mut self, float: &exam2::Float) {
+        self.visitor.visit_float(&exam3::Float(float.0));
+    }
+
+    fn visit_big_int(&mut self, big_int: &exam2::BigInt) {
+        self.visitor.visit_big_int(&exam3::BigInt(big_int.0.clone()));
+    }
+
+    fn visit_rational(&mut self, rational: &exam2::Rational) {
+        self.visitor.visit_rational(&exam3::Rational(rational.0.clone()));
+    }
+
+    fn visit_decimal(&mut self, decimal: &exam2::Decimal) {
+        self.visitor.visit_decimal(&exam3::Decimal(decimal.0.clone()));
+    }
+
+    fn visit_complex(&mut self, complex: &exam2::Complex) {
+        self.visitor.visit_complex(&exam3::Complex(complex.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::num;
+    use crate::visitor::Control;
+
+    #[test]
+    fn bridge() {
+        // Accepts a visitor as an `Int` of its value if any, but is not an `exam2::Int`.
+        #[derive(Debug, PartialEq, PartialOrd)]
+        struct Foreign(Option<i32>);
+
+        impl exam2::Number for Foreign {
+            fn as_dyn_eq(&self) -> &dyn dyn_ord::DynEq {
+                self
+            }
+
+            fn as_dyn_ord(&self) -> &dyn dyn_ord::DynOrd {
+                self
+            }
+
+            fn as_f64(&self) -> f64 {
+                f64::NAN
+            }
+
+            fn fold(self: Box<Self>, _folder: &mut dyn exam2::Folder) -> Box<dyn exam2::Number> {
+                self
+            }
+        }
+
+        impl exam2::Element for Foreign {
+            fn accept_at(&mut self, _visitor: &mut dyn exam2::Visitor, _depth: usize) -> Control {
+                Control::Continue
+            }
+
+            fn accept_ref_at(&self, visitor: &mut dyn exam2::Visit, _depth: usize) -> Control {
+                if let Some(int) = self.0 {
+                    visitor.visit_int(&exam2::Int(int));
+                }
+                Control::Continue
+            }
+        }
+
+        let numbers = exam3::parse_numbers("1, 2.5, 99999999999, NaN").unwrap().into_iter()
+            .chain([exam3::Number::Decimal(exam3::Decimal("0.1".parse().unwrap())), exam3::Number::Complex(exam3::Complex(num::Complex::new(1.0, -2.0)))]);
+        for number in numbers {
+            let converted: Box<dyn exam2::Number> = number.clone().into();
+            let back = exam3::Number::try_from(converted).unwrap();
+            assert!(back == number || back.as_f64().is_nan() && number.as_f64().is_nan());
+        }
+        let error = exam3::Number::try_from(Box::new(Foreign(None)) as Box<dyn exam2::Number>).unwrap_err();
+        assert_eq!(error.to_string(), "unknown kind of number: Foreign(None)");
+        let error = exam3::Number::try_from(Box::new(Foreign(Some(7))) as Box<dyn exam2::Number>).unwrap_err();
+        assert_eq!(error.to_string(), "unknown kind of number: Foreign(Some(7))");
+
+        let mut numbers3 = exam3::parse_numbers("1, 2.5").unwrap();
+        let mut increaser = exam2::Increaser::new(2);
+        numbers3.iter_mut().for_each(|n| n.accept(&mut Exam2Adapter::new(&mut increaser)));
+        assert_eq!(numbers3, exam3::parse_numbers("3, 4.5").unwrap());
+        let mut statistics = exam2::Statistics::new();
+        numbers3.iter().for_each(|n| n.accept_ref(&mut Exam2Adapter::new(&mut statistics)));
+        assert_eq!(statistics.mean(), Some(3.75));
+
+        let mut numbers2 = exam2::parse_numbers("1, 2.5").unwrap();
+        let mut decreaser: Box<dyn exam3::Visitor> = Box::new(exam3::Decreaser::new(1));
+        for n in numbers2.iter_mut() {
+            n.accept(&mut Exam3Adapter::new(decreaser.as_mut()));
+        }
+        assert_eq!(numbers2, exam2::parse_numbers("0, 1.5").unwrap());
+        let mut statistics = exam3::Statistics::new();
+        for n in numbers2.iter() {
+            n.accept_ref(&mut Exam3Adapter::new(&mut statistics));
+        }
+        assert_eq!((statistics.count(), statistics.mean()), (2, Some(0.75)));
+    }
+}
@@ -1,21 +1,77 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::{fmt, result};
+use crate::utils::iter::NoneIterator;
+use gof_derive::{visitor, Visitable};
+use crate::visitor::{Accept, ByTry, ByValue, ByVisitor, Control, FanOut, transpose};
 
-pub type Watt = u32;
-pub type Currency = u32;
+use crate::composite::exam1 as composite;
+use crate::composite::exam1::Equipment as CompositeEquipment;
 
-pub trait Equipment {
+// NOTE: The equipment model shares its types with `composite::exam1`, and offers the same
+//       collection operations. A tree built and edited with the composite API is visited by `accept_composite`
+//       and `accept_composite_mut`.
+pub use crate::composite::exam1::{Currency, Error, Result, Watt};
+
+// Generates the trait `Element` accepting the visitors, and the traits `EquipmentVisitor` and `EquipmentVisit`,
+// whose visit methods return `Control`, so that a visitor can stop the traversal at an equipment it fails on.
+visitor! {
+    accept = Element, visit_mut = EquipmentVisitor, visit = EquipmentVisit, control = Control;
+    FloppyDisk -> Control,
+    Chassis { .. } -> Control,
+}
+
+pub type Parts<'a> = Box<dyn Iterator<Item = &'a Box<dyn Equipment + 'a>> + 'a>;
+
+pub trait Equipment: Element {
+    // Basic Operations
     fn name(&self) -> &str;
     fn power(&self) -> Watt;
     fn net_price(&self) -> Currency;
     fn discount_price(&self) -> Currency;
-    fn accept(&mut self, visitor: &mut dyn EquipmentVisitor);
+
+    // The stock keeping unit, which tells apart equipments of the same kind and name.
+    fn sku(&self) -> Option<&str> {
+        None
+    }
+
+    // Collection Operations
+    fn add(&mut self, #[allow(unused)] part: Box<dyn Equipment>) -> Result<()> {
+        Err("function `Equipment::add` cannot be applied to a leaf equipment")
+    }
+
+    fn remove(&mut self, #[allow(unused)] part_name: &str) -> Result<()> {
+        Err("function `Equipment::remove` cannot be applied to a leaf equipment")
+    }
+
+    fn parts(&self) -> Parts<'_> {
+        Box::new(NoneIterator::new())
+    }
 }
 
+#[derive(Visitable)]
 pub struct FloppyDisk {
     name: String,
     power: Watt,
     net_price: Currency,
     discount_price: Currency,
+    sku: Option<String>,
+}
+
+impl FloppyDisk {
+    pub fn new(name: &str, power: Watt, net_price: Currency, discount_price: Currency) -> Self {
+        FloppyDisk {
+            name: name.to_string(),
+            power,
+            net_price,
+            discount_price,
+            sku: None,
+        }
+    }
+
+    pub fn with_sku(mut self, sku: &str) -> Self {
+        self.sku = Some(sku.to_string());
+        self
+    }
 }
 
 impl Equipment for FloppyDisk {
@@ -35,19 +91,40 @@ impl Equipment for FloppyDisk {
         self.discount_price
     }
 
-    fn accept(&mut self, visitor: &mut dyn EquipmentVisitor) {
-        visitor.visit_floppy_disk(self);
+    fn sku(&self) -> Option<&str> {
+        self.sku.as_deref()
     }
 }
 
+#[derive(Visitable)]
 pub struct Chassis {
     name: String,
     power: Watt,
     net_price: Currency,
     discount_price: Currency,
+    sku: Option<String>,
+    #[visitable(children)]
     parts: Vec<Box<dyn Equipment>>,
 }
 
+impl Chassis {
+    pub fn new(name: &str, power: Watt, net_price: Currency, discount_price: Currency) -> Self {
+        Chassis {
+            name: name.to_string(),
+            power,
+            net_price,
+            discount_price,
+            sku: None,
+            parts: Vec::new(),
+        }
+    }
+
+    pub fn with_sku(mut self, sku: &str) -> Self {
+        self.sku = Some(sku.to_string());
+        self
+    }
+}
+
 impl Equipment for Chassis {
     fn name(&self) -> &str {
         &self.name
@@ -65,66 +142,830 @@ impl Equipment for Chassis {
         self.discount_price
     }
 
-    fn accept(&mut self, visitor: &mut dyn EquipmentVisitor) {
-        for part in self.parts.iter_mut() {
-            part.accept(visitor);
+    fn sku(&self) -> Option<&str> {
+        self.sku.as_deref()
+    }
+
+    fn add(&mut self, part: Box<dyn Equipment>) -> Result<()> {
+        self.parts.push(part);
+        Ok(())
+    }
+
+    fn remove(&mut self, part_name: &str) -> Result<()> {
+        let pos = self.parts.iter().position(|part| part.name() == part_name);
+        match pos {
+            Some(pos) => {
+                self.parts.remove(pos);
+                Ok(())
+            },
+            None => Err("part not found for name"),
+        }
+    }
+
+    fn parts(&self) -> Parts<'_> {
+        Box::new(self.parts.iter())
+    }
+}
+
+// Converts a tree built with the `composite::exam1` API into the equipments of this module, parts and all.
+// A composite chassis reports the totals of itself and its parts, so the power and prices of the converted `Chassis`
+// are those of the composite chassis minus those of its parts, and it fails if they are less than those of its parts.
+pub fn from_composite(equipment: &dyn CompositeEquipment) -> Result<Box<dyn Equipment>> {
+    if !equipment.is_composite() {
+        return Ok(Box::new(FloppyDisk::new(equipment.name(), equipment.power(), equipment.net_price(), equipment.discount_price())));
+    }
+
+    let own = |total: u32, of: fn(&dyn CompositeEquipment) -> u32| {
+        equipment.parts()
+            .try_fold(0u32, |sum, part| sum.checked_add(of(part.as_ref())))
+            .and_then(|parts| total.checked_sub(parts))
+            .ok_or("the totals of a composite chassis are less than those of its parts")
+    };
+    let mut chassis = Chassis::new(
+        equipment.name(),
+        own(equipment.power(), |part| part.power())?,
+        own(equipment.net_price(), |part| part.net_price())?,
+        own(equipment.discount_price(), |part| part.discount_price())?,
+    );
+    for part in equipment.parts() {
+        chassis.add(from_composite(part.as_ref())?)?;
+    }
+    Ok(Box::new(chassis))
+}
+
+// Converts equipments of this module into a tree of `composite::exam1`, which keeps no SKUs.
+pub fn into_composite(equipment: &mut dyn Equipment) -> Box<dyn CompositeEquipment> {
+    equipment.accept_value(&mut CompositeBuilder)
+}
+
+struct CompositeBuilder;
+
+impl EquipmentValueVisitor<Box<dyn CompositeEquipment>> for CompositeBuilder {
+    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) -> Box<dyn CompositeEquipment> {
+        let FloppyDisk { name, power, net_price, discount_price, .. } = floppy_disk;
+        Box::new(composite::FloppyDisk::new(name, *power, *net_price, *discount_price))
+    }
+
+    fn visit_chassis(&mut self, chassis: &mut Chassis, parts: Vec<Box<dyn CompositeEquipment>>) -> Box<dyn CompositeEquipment> {
+        let mut composite = composite::Chassis::new(&chassis.name, chassis.power, chassis.net_price, chassis.discount_price);
+        for part in parts {
+            composite.add(part).expect("a composite chassis must accept parts");
+        }
+        Box::new(composite)
+    }
+}
+
+// Visits a tree built with the `composite::exam1` API, such as `accept_composite(&chassis, &mut PricingVisitor::new())`,
+// as converted by `from_composite`.
+pub fn accept_composite(equipment: &dyn CompositeEquipment, visitor: &mut dyn EquipmentVisit) -> Result<Control> {
+    Ok(from_composite(equipment)?.accept_ref(visitor))
+}
+
+// Visits a tree built with the `composite::exam1` API mutably, and replaces it with the visited tree,
+// so that the changes of the visitor, such as parts added or removed, are kept.
+// NOTE: The composite API cannot change an equipment in place, so the tree is rebuilt of the types of `composite::exam1`.
+pub fn accept_composite_mut(equipment: &mut Box<dyn CompositeEquipment>, visitor: &mut dyn EquipmentVisitor) -> Result<Control> {
+    let mut converted = from_composite(equipment.as_ref())?;
+    let control = converted.accept(visitor);
+    *equipment = into_composite(converted.as_mut());
+    Ok(control)
+}
+
+pub trait EquipmentValueVisitor<R> {
+    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) -> R;
+    fn visit_chassis(&mut self, chassis: &mut Chassis, parts: Vec<R>) -> R;
+}
+
+impl dyn Equipment + '_ {
+    pub fn accept_value<R>(&mut self, visitor: &mut dyn EquipmentValueVisitor<R>) -> R {
+        let mut adapter = ValueAdapter { visitor, results: Vec::new() };
+        self.accept(&mut adapter);
+        adapter.results.pop().expect("an accepted equipment must produce a result")
+    }
+}
+
+struct ValueAdapter<'a, R> {
+    visitor: &'a mut dyn EquipmentValueVisitor<R>,
+    results: Vec<R>,
+}
+
+impl<R> EquipmentVisitor for ValueAdapter<'_, R> {
+    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) -> Control {
+        let result = self.visitor.visit_floppy_disk(floppy_disk);
+        self.results.push(result);
+        Control::Continue
+    }
+
+    fn visit_chassis(&mut self, chassis: &mut Chassis) -> Control {
+        let parts = self.results.split_off(self.results.len() - chassis.parts.len());
+        let result = self.visitor.visit_chassis(chassis, parts);
+        self.results.push(result);
+        Control::Continue
+    }
+}
+
+// A visitor which can fail. `try_accept` stops at the first error, and reports it with the path to the failing equipment.
+pub trait TryEquipmentVisitor<E> {
+    fn visit_floppy_disk(&mut self, _floppy_disk: &mut FloppyDisk) -> result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_chassis(&mut self, _chassis: &mut Chassis) -> result::Result<(), E> {
+        Ok(())
+    }
+
+    // Hooks
+    fn enter_chassis(&mut self, _chassis: &mut Chassis, _depth: usize) -> result::Result<Control, E> {
+        Ok(Control::Continue)
+    }
+
+    fn leave_chassis(&mut self, _chassis: &mut Chassis, _depth: usize) -> result::Result<(), E> {
+        Ok(())
+    }
+}
+
+pub trait TryEquipmentVisit<E> {
+    fn visit_floppy_disk(&mut self, _floppy_disk: &FloppyDisk) -> result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_chassis(&mut self, _chassis: &Chassis) -> result::Result<(), E> {
+        Ok(())
+    }
+
+    // Hooks
+    fn enter_chassis(&mut self, _chassis: &Chassis, _depth: usize) -> result::Result<Control, E> {
+        Ok(Control::Continue)
+    }
+
+    fn leave_chassis(&mut self, _chassis: &Chassis, _depth: usize) -> result::Result<(), E> {
+        Ok(())
+    }
+}
+
+// Reports the first error of a fallible visitor.
+// `path` holds the names of the equipments from the visited one down to the failing one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisitError<E> {
+    pub path: Vec<String>,
+    pub error: E,
+}
+
+impl<E: fmt::Display> fmt::Display for VisitError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.join("/"), self.error)
+    }
+}
+
+impl dyn Equipment + '_ {
+    pub fn try_accept<E>(&mut self, visitor: &mut dyn TryEquipmentVisitor<E>) -> result::Result<Control, VisitError<E>> {
+        let mut adapter = TryAdapter::new(visitor);
+        let control = self.accept(&mut adapter);
+        adapter.error.map_or(Ok(control), Err)
+    }
+
+    pub fn try_accept_ref<E>(&self, visitor: &mut dyn TryEquipmentVisit<E>) -> result::Result<Control, VisitError<E>> {
+        let mut adapter = TryAdapter::new(visitor);
+        let control = self.accept_ref(&mut adapter);
+        adapter.error.map_or(Ok(control), Err)
+    }
+}
+
+// Drives a fallible visitor by the infallible traversal, which the adapter stops once the visitor fails.
+struct TryAdapter<'a, V: ?Sized, E> {
+    visitor: &'a mut V,
+    path: Vec<String>,
+    error: Option<VisitError<E>>,
+}
+
+impl<'a, V: ?Sized, E> TryAdapter<'a, V, E> {
+    fn new(visitor: &'a mut V) -> Self {
+        TryAdapter { visitor, path: Vec::new(), error: None }
+    }
+
+    // Calls the visitor unless it has failed, and records its error with the path.
+    // Returns `None` if the visitor has failed, now or before.
+    fn call<T>(&mut self, f: impl FnOnce(&mut V) -> result::Result<T, E>) -> Option<T> {
+        if self.error.is_some() {
+            return None;
+        }
+        match f(self.visitor) {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.error = Some(VisitError { path: self.path.clone(), error });
+                None
+            },
+        }
+    }
+
+    // Calls the visitor for a leaf, and stops the traversal if it has failed,
+    // adding the leaf to the path of the error if it fails in this call.
+    fn leaf(&mut self, name: String, f: impl FnOnce(&mut V) -> result::Result<(), E>) -> Control {
+        let failed = self.error.is_some();
+        if self.call(f).is_some() {
+            return Control::Continue;
+        }
+        if let Some(error) = self.error.as_mut().filter(|_| !failed) {
+            error.path.push(name);
+        }
+        Control::Stop
+    }
+
+    // Calls the visitor for the innermost open chassis, which is at the end of the path,
+    // and stops the traversal if it has failed.
+    fn composite(&mut self, f: impl FnOnce(&mut V) -> result::Result<(), E>) -> Control {
+        if self.call(f).is_some() { Control::Continue } else { Control::Stop }
+    }
+
+    fn enter(&mut self, name: String, f: impl FnOnce(&mut V) -> result::Result<Control, E>) -> Control {
+        self.path.push(name);
+        match self.call(f) {
+            Some(Control::Stop) | None => Control::Stop,
+            Some(control) => control,
+        }
+    }
+
+    // A failure in leaving stops the traversal at the next callback, which the visitor is not called for.
+    fn leave(&mut self, f: impl FnOnce(&mut V) -> result::Result<(), E>) {
+        self.call(f);
+        self.path.pop();
+    }
+}
+
+impl<E> EquipmentVisitor for TryAdapter<'_, dyn TryEquipmentVisitor<E> + '_, E> {
+    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) -> Control {
+        self.leaf(floppy_disk.name().to_string(), |visitor| visitor.visit_floppy_disk(floppy_disk))
+    }
+
+    fn visit_chassis(&mut self, chassis: &mut Chassis) -> Control {
+        self.composite(|visitor| visitor.visit_chassis(chassis))
+    }
+
+    fn enter_chassis(&mut self, chassis: &mut Chassis, depth: usize) -> Control {
+        self.enter(chassis.name().to_string(), |visitor| visitor.enter_chassis(chassis, depth))
+    }
+
+    fn leave_chassis(&mut self, chassis: &mut Chassis, depth: usize) {
+        self.leave(|visitor| visitor.leave_chassis(chassis, depth));
+    }
+}
+
+impl<E> EquipmentVisit for TryAdapter<'_, dyn TryEquipmentVisit<E> + '_, E> {
+    fn visit_floppy_disk(&mut self, floppy_disk: &FloppyDisk) -> Control {
+        self.leaf(floppy_disk.name().to_string(), |visitor| visitor.visit_floppy_disk(floppy_disk))
+    }
+
+    fn visit_chassis(&mut self, chassis: &Chassis) -> Control {
+        self.composite(|visitor| visitor.visit_chassis(chassis))
+    }
+
+    fn enter_chassis(&mut self, chassis: &Chassis, depth: usize) -> Control {
+        self.enter(chassis.name().to_string(), |visitor| visitor.enter_chassis(chassis, depth))
+    }
+
+    fn leave_chassis(&mut self, chassis: &Chassis, depth: usize) {
+        self.leave(|visitor| visitor.leave_chassis(chassis, depth));
+    }
+}
+
+impl<V: EquipmentVisitor> Accept<V, ByVisitor> for &mut Box<dyn Equipment + '_> {
+    type Output = Control;
+
+    fn accept_with(self, visitor: &mut V) -> Control {
+        self.accept(visitor)
+    }
+}
+
+impl<V: EquipmentVisit> Accept<V, ByVisitor> for &Box<dyn Equipment + '_> {
+    type Output = Control;
+
+    fn accept_with(self, visitor: &mut V) -> Control {
+        self.accept_ref(visitor)
+    }
+}
+
+impl<R, V: EquipmentValueVisitor<R>> Accept<V, ByValue<R>> for &mut Box<dyn Equipment + '_> {
+    type Output = R;
+
+    fn accept_with(self, visitor: &mut V) -> R {
+        self.accept_value(visitor)
+    }
+}
+
+impl<E, V: TryEquipmentVisitor<E>> Accept<V, ByTry<E>> for &mut Box<dyn Equipment + '_> {
+    type Output = result::Result<Control, VisitError<E>>;
+
+    fn accept_with(self, visitor: &mut V) -> Self::Output {
+        self.try_accept(visitor)
+    }
+}
+
+impl<E, V: TryEquipmentVisit<E>> Accept<V, ByTry<E>> for &Box<dyn Equipment + '_> {
+    type Output = result::Result<Control, VisitError<E>>;
+
+    fn accept_with(self, visitor: &mut V) -> Self::Output {
+        self.try_accept_ref(visitor)
+    }
+}
+
+impl EquipmentVisitor for FanOut<'_, dyn EquipmentVisitor + '_> {
+    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) -> Control {
+        self.leaf_control(|visitor| visitor.visit_floppy_disk(floppy_disk))
+    }
+
+    fn visit_chassis(&mut self, chassis: &mut Chassis) -> Control {
+        self.composite_control(|visitor| visitor.visit_chassis(chassis))
+    }
+
+    fn enter_chassis(&mut self, chassis: &mut Chassis, depth: usize) -> Control {
+        self.enter(|visitor| visitor.enter_chassis(chassis, depth))
+    }
+
+    fn leave_chassis(&mut self, chassis: &mut Chassis, depth: usize) {
+        self.leave(|visitor| visitor.leave_chassis(chassis, depth));
+    }
+}
+
+impl EquipmentVisit for FanOut<'_, dyn EquipmentVisit + '_> {
+    fn visit_floppy_disk(&mut self, floppy_disk: &FloppyDisk) -> Control {
+        self.leaf_control(|visitor| visitor.visit_floppy_disk(floppy_disk))
+    }
+
+    fn visit_chassis(&mut self, chassis: &Chassis) -> Control {
+        self.composite_control(|visitor| visitor.visit_chassis(chassis))
+    }
+
+    fn enter_chassis(&mut self, chassis: &Chassis, depth: usize) -> Control {
+        self.enter(|visitor| visitor.enter_chassis(chassis, depth))
+    }
+
+    fn leave_chassis(&mut self, chassis: &Chassis, depth: usize) {
+        self.leave(|visitor| visitor.leave_chassis(chassis, depth));
+    }
+}
+
+impl<R> EquipmentValueVisitor<Vec<R>> for FanOut<'_, dyn EquipmentValueVisitor<R> + '_> {
+    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_floppy_disk(floppy_disk))
+    }
+
+    fn visit_chassis(&mut self, chassis: &mut Chassis, parts: Vec<Vec<R>>) -> Vec<R> {
+        let columns = transpose(parts, self.visitors.len());
+        self.visitors.iter_mut().zip(columns).map(|(visitor, parts)| visitor.visit_chassis(chassis, parts)).collect()
+    }
+}
+
+// The kinds of equipments, which the visitors tell apart by their callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Kind {
+    FloppyDisk,
+    Chassis,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::FloppyDisk => "floppy_disk",
+            Kind::Chassis => "chassis",
         }
-        visitor.visit_chassis(self);
     }
 }
 
-pub trait EquipmentVisitor {
-    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk);
-    fn visit_chassis(&mut self, chassis: &mut Chassis);
+// A rate in basis points, hundredths of a percent, so that `Rate::percent(8)` equals `Rate::basis_points(800)`.
+// NOTE: The basis points are kept in `u64`, so that any percent of `u32` can be represented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Rate(u64);
+
+impl Rate {
+    pub const ZERO: Rate = Rate(0);
+
+    pub fn percent(percent: u32) -> Self {
+        Rate(percent as u64 * 100)
+    }
+
+    pub fn basis_points(basis_points: u32) -> Self {
+        Rate(basis_points as u64)
+    }
+
+    // Returns the rate of an amount, rounded half up, or an error if it overflows the currency.
+    pub fn of(&self, amount: Currency) -> Result<Currency> {
+        let rate = (amount as u128 * self.0 as u128 + 5_000) / 10_000;
+        Currency::try_from(rate).map_err(|_| OVERFLOW)
+    }
+}
+
+const OVERFLOW: Error = "amount overflows the currency";
+
+fn checked_sum(mut amounts: impl Iterator<Item = Currency>) -> Result<Currency> {
+    amounts.try_fold(0, |sum: Currency, amount| sum.checked_add(amount).ok_or(OVERFLOW))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PriceRule {
+    Net,
+    Discount,
+    // The net price less a rate of it.
+    Off(Rate),
+    // The net price less the rate of the highest tier whose minimum quantity is reached
+    // by the quantity of the equipments of the same item, which is the kind, name and SKU, in the quote.
+    Tiers(Vec<(Quantity, Rate)>),
+}
+
+impl PriceRule {
+    fn price(&self, net_price: Currency, discount_price: Currency, quantity: Quantity) -> Currency {
+        let off = match self {
+            PriceRule::Net => Rate::ZERO,
+            PriceRule::Discount => return discount_price,
+            PriceRule::Off(rate) => *rate,
+            PriceRule::Tiers(tiers) => tiers.iter()
+                .filter(|(min, _)| *min <= quantity)
+                .max_by_key(|(min, _)| *min)
+                .map_or(Rate::ZERO, |(_, rate)| *rate),
+        };
+        // A rate off which overflows the currency is more than the net price.
+        off.of(net_price).map_or(0, |off| net_price.saturating_sub(off))
+    }
+}
+
+// The price rule and the tax rate of each kind of equipment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PricingPolicy {
+    floppy_disk: (PriceRule, Rate),
+    chassis: (PriceRule, Rate),
+}
+
+impl PricingPolicy {
+    // Prices floppy disks by net price and chassis by discount price, without tax.
+    pub fn new() -> Self {
+        PricingPolicy {
+            floppy_disk: (PriceRule::Net, Rate::ZERO),
+            chassis: (PriceRule::Discount, Rate::ZERO),
+        }
+    }
+
+    pub fn with_rule(mut self, kind: Kind, rule: PriceRule) -> Self {
+        self.kind_mut(kind).0 = rule;
+        self
+    }
+
+    pub fn with_tax(mut self, kind: Kind, tax: Rate) -> Self {
+        self.kind_mut(kind).1 = tax;
+        self
+    }
+
+    pub fn rule(&self, kind: Kind) -> &PriceRule {
+        &self.kind(kind).0
+    }
+
+    pub fn tax(&self, kind: Kind) -> Rate {
+        self.kind(kind).1
+    }
+
+    fn kind(&self, kind: Kind) -> &(PriceRule, Rate) {
+        match kind {
+            Kind::FloppyDisk => &self.floppy_disk,
+            Kind::Chassis => &self.chassis,
+        }
+    }
+
+    fn kind_mut(&mut self, kind: Kind) -> &mut (PriceRule, Rate) {
+        match kind {
+            Kind::FloppyDisk => &mut self.floppy_disk,
+            Kind::Chassis => &mut self.chassis,
+        }
+    }
+}
+
+impl Default for PricingPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// An equipment priced by a quote. `depth` is the number of chassis enclosing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineItem {
+    pub name: String,
+    pub kind: Kind,
+    pub depth: usize,
+    pub price: Currency,
+    pub tax: Currency,
+}
+
+// The sum of the line items of a chassis, including the chassis itself and all its parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subtotal {
+    pub name: String,
+    pub depth: usize,
+    pub price: Currency,
+    pub tax: Currency,
+}
+
+impl Subtotal {
+    pub fn total(&self) -> Result<Currency> {
+        self.price.checked_add(self.tax).ok_or(OVERFLOW)
+    }
+}
+
+// The line items in the order of traversal, each chassis before its parts, and the subtotals of the chassis in the same order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Quote {
+    pub items: Vec<LineItem>,
+    pub subtotals: Vec<Subtotal>,
+}
+
+// The sums return an error if they overflow the currency.
+impl Quote {
+    pub fn price(&self) -> Result<Currency> {
+        checked_sum(self.items.iter().map(|item| item.price))
+    }
+
+    pub fn tax(&self) -> Result<Currency> {
+        checked_sum(self.items.iter().map(|item| item.tax))
+    }
+
+    pub fn total(&self) -> Result<Currency> {
+        self.price()?.checked_add(self.tax()?).ok_or(OVERFLOW)
+    }
+}
+
+// An equipment visited by a `PricingVisitor`, which is priced when quoted,
+// because a volume tier depends on the quantity of the equipment in the whole quote.
+struct Entry {
+    item: Item,
+    net_price: Currency,
+    discount_price: Currency,
+    // The indexes of the entries of the enclosing chassis, outermost first.
+    chassis: Vec<usize>,
 }
 
 pub struct PricingVisitor {
-    total: Currency,
+    policy: PricingPolicy,
+    entries: Vec<Entry>,
+    open: Vec<usize>,
 }
 
 impl PricingVisitor {
     pub fn new() -> Self {
-        PricingVisitor { total: 0 }
+        Self::with_policy(PricingPolicy::new())
+    }
+
+    pub fn with_policy(policy: PricingPolicy) -> Self {
+        PricingVisitor {
+            policy,
+            entries: Vec::new(),
+            open: Vec::new(),
+        }
     }
 
-    pub fn total_price(&self) -> Currency {
-        self.total
+    pub fn total_price(&self) -> Result<Currency> {
+        self.quote()?.total()
+    }
+
+    // Returns an error if a tax or a subtotal overflows the currency.
+    pub fn quote(&self) -> Result<Quote> {
+        let mut quantities: HashMap<&Item, Quantity> = HashMap::new();
+        for entry in self.entries.iter() {
+            *quantities.entry(&entry.item).or_insert(0) += 1;
+        }
+        let mut quote = Quote::default();
+        let mut subtotals = HashMap::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            let Item { kind, name, .. } = &entry.item;
+            let (rule, tax) = self.policy.kind(*kind);
+            let price = rule.price(entry.net_price, entry.discount_price, quantities[&entry.item]);
+            let tax = tax.of(price)?;
+            if *kind == Kind::Chassis {
+                subtotals.insert(i, quote.subtotals.len());
+                quote.subtotals.push(Subtotal { name: name.clone(), depth: entry.chassis.len(), price: 0, tax: 0 });
+            }
+            for chassis in entry.chassis.iter().chain((*kind == Kind::Chassis).then_some(&i)) {
+                let subtotal = &mut quote.subtotals[subtotals[chassis]];
+                subtotal.price = subtotal.price.checked_add(price).ok_or(OVERFLOW)?;
+                subtotal.tax = subtotal.tax.checked_add(tax).ok_or(OVERFLOW)?;
+            }
+            quote.items.push(LineItem { name: name.clone(), kind: *kind, depth: entry.chassis.len(), price, tax });
+        }
+        Ok(quote)
+    }
+
+    fn push(&mut self, kind: Kind, equipment: &dyn Equipment) {
+        self.entries.push(Entry {
+            item: Item::of(kind, equipment),
+            net_price: equipment.net_price(),
+            discount_price: equipment.discount_price(),
+            chassis: self.open.clone(),
+        });
+    }
+
+    fn enter(&mut self, chassis: &Chassis) -> Control {
+        self.push(Kind::Chassis, chassis);
+        self.open.push(self.entries.len() - 1);
+        Control::Continue
     }
 }
 
+impl Default for PricingVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// NOTE: A chassis is priced when entered, so that the items of a quote list each chassis before its parts.
 impl EquipmentVisitor for PricingVisitor {
-    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) {
-        self.total += floppy_disk.net_price();
+    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) -> Control {
+        self.push(Kind::FloppyDisk, floppy_disk);
+        Control::Continue
+    }
+
+    fn enter_chassis(&mut self, chassis: &mut Chassis, _depth: usize) -> Control {
+        self.enter(chassis)
     }
 
-    fn visit_chassis(&mut self, chassis: &mut Chassis) {
-        self.total += chassis.discount_price();
+    fn leave_chassis(&mut self, _chassis: &mut Chassis, _depth: usize) {
+        self.open.pop();
+    }
+}
+
+impl EquipmentVisit for PricingVisitor {
+    fn visit_floppy_disk(&mut self, floppy_disk: &FloppyDisk) -> Control {
+        self.push(Kind::FloppyDisk, floppy_disk);
+        Control::Continue
+    }
+
+    fn enter_chassis(&mut self, chassis: &Chassis, _depth: usize) -> Control {
+        self.enter(chassis)
+    }
+
+    fn leave_chassis(&mut self, _chassis: &Chassis, _depth: usize) {
+        self.open.pop();
+    }
+}
+
+// Prices an equipment like `PricingVisitor::new`, and returns an error if the price overflows the currency.
+pub struct PriceCalculator;
+
+impl EquipmentValueVisitor<Result<Currency>> for PriceCalculator {
+    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) -> Result<Currency> {
+        Ok(floppy_disk.net_price())
+    }
+
+    fn visit_chassis(&mut self, chassis: &mut Chassis, parts: Vec<Result<Currency>>) -> Result<Currency> {
+        let parts = parts.into_iter().collect::<Result<Vec<Currency>>>()?;
+        checked_sum(parts.into_iter().chain([chassis.discount_price()]))
     }
 }
 
 pub type Quantity = u32;
 
+// Identifies the equipments counted together by an inventory, which have the same kind, name and SKU.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Item {
+    pub kind: Kind,
+    pub name: String,
+    pub sku: Option<String>,
+}
+
+impl Item {
+    pub fn new(kind: Kind, name: &str) -> Self {
+        Item { kind, name: name.to_string(), sku: None }
+    }
+
+    pub fn with_sku(mut self, sku: &str) -> Self {
+        self.sku = Some(sku.to_string());
+        self
+    }
+
+    fn of(kind: Kind, equipment: &dyn Equipment) -> Self {
+        Item {
+            kind,
+            name: equipment.name().to_string(),
+            sku: equipment.sku().map(str::to_string),
+        }
+    }
+}
+
+// An item whose stock is below its reorder threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reorder {
+    pub item: Item,
+    pub stock: Quantity,
+    pub threshold: Quantity,
+}
+
+impl Reorder {
+    // Returns 0 if the stock is not below the threshold.
+    pub fn shortfall(&self) -> Quantity {
+        self.threshold.saturating_sub(self.stock)
+    }
+}
+
+// Counts equipments by item, listed in the order of kind, name and SKU.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Inventory {
-    equipments: HashMap<String, Quantity>,
+    equipments: BTreeMap<Item, Quantity>,
 }
 
 impl Inventory {
     pub fn new() -> Self {
-        Inventory { equipments: HashMap::new() }
+        Inventory { equipments: BTreeMap::new() }
     }
 
-    pub fn accumulate(&mut self, name: &str) {
-        let quantity = self.equipments.entry(name.to_string()).or_insert(0);
-        *quantity += 1;
+    pub fn accumulate(&mut self, item: Item) {
+        self.add(item, 1);
     }
 
-    pub fn list(&self) -> &HashMap<String, Quantity> {
+    pub fn add(&mut self, item: Item, quantity: Quantity) {
+        if quantity > 0 {
+            *self.equipments.entry(item).or_insert(0) += quantity;
+        }
+    }
+
+    pub fn list(&self) -> &BTreeMap<Item, Quantity> {
         &self.equipments
     }
 
-    pub fn quantity(&self, name: &str) -> Option<&Quantity> {
-        self.equipments.get(name)
+    // Returns 0 for an item not in the inventory.
+    pub fn quantity(&self, item: &Item) -> Quantity {
+        self.equipments.get(item).copied().unwrap_or(0)
+    }
+
+    pub fn merge(&mut self, other: &Inventory) {
+        for (item, quantity) in other.equipments.iter() {
+            self.add(item.clone(), *quantity);
+        }
+    }
+
+    // Takes the quantities of another inventory out of this one, and removes the items which run out.
+    // Fails without changing this inventory if any item is short.
+    pub fn subtract(&mut self, other: &Inventory) -> Result<()> {
+        if other.equipments.iter().any(|(item, quantity)| self.quantity(item) < *quantity) {
+            return Err("inventory to subtract exceeds stock");
+        }
+        for (item, quantity) in other.equipments.iter() {
+            if let Some(stock) = self.equipments.get_mut(item) {
+                *stock -= quantity;
+                if *stock == 0 {
+                    self.equipments.remove(item);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the items whose stock in this inventory is below their quantity in `thresholds`, in the order of items.
+    pub fn reorders(&self, thresholds: &Inventory) -> Vec<Reorder> {
+        thresholds.equipments.iter()
+            .map(|(item, threshold)| Reorder { item: item.clone(), stock: self.quantity(item), threshold: *threshold })
+            .filter(|reorder| reorder.stock < reorder.threshold)
+            .collect()
+    }
+
+    // Exports the inventory as CSV with the header `kind,name,sku,quantity`, quoting the fields as in RFC 4180.
+    // `sku` is left empty for an item without SKU, and an empty SKU is quoted as `""`, so that they are told apart.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("kind,name,sku,quantity\n");
+        for (item, quantity) in self.equipments.iter() {
+            let sku = item.sku.as_deref().map_or(String::new(), csv_field);
+            csv += &format!("{},{},{},{}\n", item.kind.as_str(), csv_field(&item.name), sku, quantity);
+        }
+        csv
+    }
+
+    // Exports the inventory as a JSON array of objects with the keys `kind`, `name`, `sku` and `quantity`,
+    // where `sku` is `null` for an item without SKU.
+    pub fn to_json(&self) -> String {
+        let items: Vec<String> = self.equipments.iter().map(|(item, quantity)| {
+            let sku = item.sku.as_deref().map_or("null".to_string(), json_string);
+            format!(r#"  {{"kind": "{}", "name": {}, "sku": {}, "quantity": {}}}"#, item.kind.as_str(), json_string(&item.name), sku, quantity)
+        }).collect();
+        if items.is_empty() { "[]".to_string() } else { format!("[\n{}\n]", items.join(",\n")) }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.is_empty() || field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            '\r' => json += "\\r",
+            '\t' => json += "\\t",
+            c if c.is_control() => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
     }
+    json.push('"');
+    json
 }
 
 pub struct InventoryVisitor {
@@ -141,19 +982,234 @@ impl InventoryVisitor {
     }
 }
 
+impl Default for InventoryVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EquipmentVisitor for InventoryVisitor {
-    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) {
-        self.inventory.accumulate(floppy_disk.name());
+    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) -> Control {
+        self.inventory.accumulate(Item::of(Kind::FloppyDisk, floppy_disk));
+        Control::Continue
+    }
+
+    fn visit_chassis(&mut self, chassis: &mut Chassis) -> Control {
+        self.inventory.accumulate(Item::of(Kind::Chassis, chassis));
+        Control::Continue
+    }
+}
+
+impl EquipmentVisit for InventoryVisitor {
+    fn visit_floppy_disk(&mut self, floppy_disk: &FloppyDisk) -> Control {
+        self.inventory.accumulate(Item::of(Kind::FloppyDisk, floppy_disk));
+        Control::Continue
     }
 
-    fn visit_chassis(&mut self, chassis: &mut Chassis) {
-        self.inventory.accumulate(chassis.name());
+    fn visit_chassis(&mut self, chassis: &Chassis) -> Control {
+        self.inventory.accumulate(Item::of(Kind::Chassis, chassis));
+        Control::Continue
+    }
+}
+
+// Watts of heat per BTU per hour.
+const WATT_PER_BTU_PER_HOUR: f64 = 0.293_071_07;
+
+// The power of an equipment in a power report. `path` starts with the index of the visited equipment
+// among the ones visited by the visitor, followed by the positions of the equipment and its enclosing chassis
+// among the parts of their chassis, so a visited equipment has a path of one index.
+// `draw` is the power of the equipment and all its parts, and `supply` is the power supply configured for a chassis.
+// NOTE: `draw` is `u64`, so that the sum of the powers of any number of parts does not overflow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerNode {
+    pub name: String,
+    pub kind: Kind,
+    pub path: Vec<usize>,
+    pub power: Watt,
+    pub draw: u64,
+    pub supply: Option<Watt>,
+    pub heat: f64,
+}
+
+impl PowerNode {
+    pub fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+
+    pub fn is_overloaded(&self) -> bool {
+        self.supply.is_some_and(|supply| self.draw > supply as u64)
+    }
+
+    pub fn heat_btu_per_hour(&self) -> f64 {
+        self.heat / WATT_PER_BTU_PER_HOUR
+    }
+}
+
+// Reports the power draw and the heat output of each equipment, each chassis before its parts,
+// and flags the chassis whose draw exceeds their supply.
+pub struct PowerVisitor {
+    supplies: HashMap<Vec<usize>, Watt>,
+    default_supply: Option<Watt>,
+    dissipation: f64,
+    nodes: Vec<PowerNode>,
+    // The number of the visited equipments, the indexes of the nodes of the open chassis,
+    // and the numbers of their parts visited so far.
+    roots: usize,
+    open: Vec<usize>,
+    parts: Vec<usize>,
+}
+
+impl PowerVisitor {
+    pub fn new() -> Self {
+        PowerVisitor {
+            supplies: HashMap::new(),
+            default_supply: None,
+            dissipation: 1.0,
+            nodes: Vec::new(),
+            roots: 0,
+            open: Vec::new(),
+            parts: Vec::new(),
+        }
+    }
+
+    // Configures the supply of the chassis at a path, which overrides the default supply.
+    // The path is that of `PowerNode`, so `&[0]` is the first visited chassis and `&[0, 1, 0]` is the first part of its second part.
+    // NOTE: Chassis are told apart by their paths, because neither their names nor their SKUs are unique,
+    //       and the paths start with the index of the visited equipment, so that the trees visited by a visitor do not collide.
+    pub fn with_supply(mut self, path: &[usize], supply: Watt) -> Self {
+        self.supplies.insert(path.to_vec(), supply);
+        self
+    }
+
+    pub fn with_default_supply(mut self, supply: Watt) -> Self {
+        self.default_supply = Some(supply);
+        self
+    }
+
+    // Configures the fraction of the power draw dissipated as heat, which is 1 by default.
+    pub fn with_dissipation(mut self, dissipation: f64) -> Result<Self> {
+        if !(0.0..=1.0).contains(&dissipation) {
+            return Err("a dissipation must be within [0, 1]");
+        }
+        self.dissipation = dissipation;
+        Ok(self)
+    }
+
+    pub fn nodes(&self) -> &[PowerNode] {
+        &self.nodes
+    }
+
+    pub fn overloaded(&self) -> impl Iterator<Item = &PowerNode> {
+        self.nodes.iter().filter(|node| node.is_overloaded())
+    }
+
+    // Returns the draw of the visited equipments, which are the ones at depth 0.
+    pub fn total_draw(&self) -> u64 {
+        self.nodes.iter().filter(|node| node.depth() == 0).map(|node| node.draw).sum()
+    }
+
+    pub fn total_heat(&self) -> f64 {
+        self.total_draw() as f64 * self.dissipation
+    }
+
+    fn push(&mut self, kind: Kind, equipment: &dyn Equipment) {
+        let path = match (self.open.last(), self.parts.last_mut()) {
+            (Some(&chassis), Some(parts)) => {
+                let mut path = self.nodes[chassis].path.clone();
+                path.push(*parts);
+                *parts += 1;
+                path
+            },
+            _ => {
+                self.roots += 1;
+                vec![self.roots - 1]
+            },
+        };
+        let supply = match kind {
+            Kind::FloppyDisk => None,
+            Kind::Chassis => self.supplies.get(&path).copied().or(self.default_supply),
+        };
+        self.nodes.push(PowerNode {
+            name: equipment.name().to_string(),
+            kind,
+            path,
+            power: equipment.power(),
+            draw: 0,
+            supply,
+            heat: 0.0,
+        });
+        if kind == Kind::FloppyDisk {
+            self.close(self.nodes.len() - 1);
+        }
+    }
+
+    fn enter(&mut self, chassis: &Chassis) -> Control {
+        self.push(Kind::Chassis, chassis);
+        self.open.push(self.nodes.len() - 1);
+        self.parts.push(0);
+        Control::Continue
+    }
+
+    fn leave(&mut self) {
+        let index = self.open.pop().expect("a chassis must be entered before left");
+        self.parts.pop();
+        self.close(index);
+    }
+
+    // Adds the own power to the draw of a node, whose parts have been added, and the draw to its chassis.
+    fn close(&mut self, index: usize) {
+        let node = &mut self.nodes[index];
+        node.draw += node.power as u64;
+        node.heat = node.draw as f64 * self.dissipation;
+        let draw = node.draw;
+        if let Some(&chassis) = self.open.last() {
+            self.nodes[chassis].draw += draw;
+        }
+    }
+}
+
+impl Default for PowerVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EquipmentVisitor for PowerVisitor {
+    fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) -> Control {
+        self.push(Kind::FloppyDisk, floppy_disk);
+        Control::Continue
+    }
+
+    fn enter_chassis(&mut self, chassis: &mut Chassis, _depth: usize) -> Control {
+        self.enter(chassis)
+    }
+
+    fn leave_chassis(&mut self, _chassis: &mut Chassis, _depth: usize) {
+        self.leave();
+    }
+}
+
+impl EquipmentVisit for PowerVisitor {
+    fn visit_floppy_disk(&mut self, floppy_disk: &FloppyDisk) -> Control {
+        self.push(Kind::FloppyDisk, floppy_disk);
+        Control::Continue
+    }
+
+    fn enter_chassis(&mut self, chassis: &Chassis, _depth: usize) -> Control {
+        self.enter(chassis)
+    }
+
+    fn leave_chassis(&mut self, _chassis: &Chassis, _depth: usize) {
+        self.leave();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::visitor::AcceptAll;
+    use std::cell::Cell;
+    use std::rc::Rc;
 
     #[test]
     fn visitor_exam1() {
@@ -163,36 +1219,42 @@ mod tests {
                 power: 1,
                 net_price: 10,
                 discount_price: 9,
+                sku: None,
             }),
             Box::new(Chassis {
                 name: "Chassis".to_string(),
                 power: 10,
                 net_price: 100,
                 discount_price: 90,
+                sku: None,
                 parts: vec![
                     Box::new(FloppyDisk {
                         name: "Floppy".to_string(),
                         power: 2,
                         net_price: 20,
                         discount_price: 19,
+                        sku: None,
                     }),
                     Box::new(FloppyDisk {
                         name: "Floppy".to_string(),
                         power: 3,
                         net_price: 30,
                         discount_price: 29,
+                        sku: None,
                     }),
                     Box::new(Chassis {
                         name: "Chassis".to_string(),
                         power: 20,
                         net_price: 200,
                         discount_price: 190,
+                        sku: None,
                         parts: vec![
                             Box::new(FloppyDisk {
                                 name: "Floppy".to_string(),
                                 power: 4,
                                 net_price: 40,
                                 discount_price: 39,
+                                sku: None,
                             }),
                         ],
                     }),
@@ -204,15 +1266,597 @@ mod tests {
         for e in equipments.iter_mut() {
             e.accept(&mut v);
         }
-        assert_eq!(v.total_price(), 380);
+        assert_eq!(v.total_price(), Ok(380));
+
+        let total: Result<Currency> = equipments.iter_mut().map(|e| e.accept_value(&mut PriceCalculator)).sum();
+        assert_eq!(total, Ok(380));
+
+        let mut v = PricingVisitor::new();
+        for e in equipments.iter() {
+            e.accept_ref(&mut v);
+        }
+        assert_eq!(v.total_price(), Ok(380));
 
         let mut v = InventoryVisitor::new();
         for e in equipments.iter_mut() {
             e.accept(&mut v);
         }
-        assert_eq!(v.inventory().list(), &HashMap::from([
-            ("Floppy".to_string(), 4),
-            ("Chassis".to_string(), 2),
+        assert_eq!(v.inventory().list(), &BTreeMap::from([
+            (Item::new(Kind::FloppyDisk, "Floppy"), 4),
+            (Item::new(Kind::Chassis, "Chassis"), 2),
         ]));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn visitor_exam1_with_composite_api() {
+        let mut chassis1 = Chassis::new("Chassis", 20, 200, 190);
+        chassis1.add(Box::new(FloppyDisk::new("Floppy", 4, 40, 39))).unwrap();
+        chassis1.add(Box::new(FloppyDisk::new("Spare", 5, 50, 49))).unwrap();
+        chassis1.remove("Spare").unwrap();
+
+        let mut chassis2 = Chassis::new("Chassis", 10, 100, 90);
+        chassis2.add(Box::new(FloppyDisk::new("Floppy", 2, 20, 19))).unwrap();
+        chassis2.add(Box::new(FloppyDisk::new("Floppy", 3, 30, 29))).unwrap();
+        chassis2.add(Box::new(chassis1)).unwrap();
+        assert_eq!(chassis2.parts().count(), 3);
+        assert!(FloppyDisk::new("Floppy", 1, 10, 9).add(Box::new(Chassis::new("Chassis", 0, 0, 0))).is_err());
+
+        let mut equipments: Vec<Box<dyn Equipment>> = vec![
+            Box::new(FloppyDisk::new("Floppy", 1, 10, 9)),
+            Box::new(chassis2),
+        ];
+
+        let mut v = PricingVisitor::new();
+        for e in equipments.iter_mut() {
+            e.accept(&mut v);
+        }
+        assert_eq!(v.total_price(), Ok(380));
+
+        let mut v = InventoryVisitor::new();
+        for e in equipments.iter_mut() {
+            e.accept(&mut v);
+        }
+        assert_eq!(v.inventory().list(), &BTreeMap::from([
+            (Item::new(Kind::FloppyDisk, "Floppy"), 4),
+            (Item::new(Kind::Chassis, "Chassis"), 2),
+        ]));
+
+        // The same tree, built and edited with the composite API.
+        let mut chassis1 = composite::Chassis::new("Chassis", 20, 200, 190);
+        chassis1.add(Box::new(composite::FloppyDisk::new("Floppy", 4, 40, 39))).unwrap();
+        chassis1.add(Box::new(composite::FloppyDisk::new("Spare", 5, 50, 49))).unwrap();
+        chassis1.remove("Spare").unwrap();
+        let mut chassis2 = composite::Chassis::new("Chassis", 10, 100, 90);
+        chassis2.add(Box::new(composite::FloppyDisk::new("Floppy", 2, 20, 19))).unwrap();
+        chassis2.add(Box::new(composite::FloppyDisk::new("Floppy", 3, 30, 29))).unwrap();
+        chassis2.add(Box::new(chassis1)).unwrap();
+        chassis2.add(Box::new(composite::Chassis::new("Empty", 1, 10, 9))).unwrap();
+        chassis2.remove("Empty").unwrap();
+        let mut composites: Vec<Box<dyn CompositeEquipment>> = vec![
+            Box::new(composite::FloppyDisk::new("Floppy", 1, 10, 9)),
+            Box::new(chassis2),
+        ];
+
+        let (mut pricing, mut inventory, mut power) = (PricingVisitor::new(), InventoryVisitor::new(), PowerVisitor::new());
+        for e in equipments.iter() {
+            e.accept_ref(&mut pricing);
+            e.accept_ref(&mut inventory);
+            e.accept_ref(&mut power);
+        }
+        let (mut composite_pricing, mut composite_inventory, mut composite_power) = (PricingVisitor::new(), InventoryVisitor::new(), PowerVisitor::new());
+        for e in composites.iter() {
+            assert_eq!(accept_composite(e.as_ref(), &mut composite_pricing), Ok(Control::Continue));
+            assert_eq!(accept_composite(e.as_ref(), &mut composite_inventory), Ok(Control::Continue));
+            assert_eq!(accept_composite(e.as_ref(), &mut composite_power), Ok(Control::Continue));
+        }
+        assert_eq!((composite_pricing.total_price(), composite_pricing.quote()), (Ok(380), pricing.quote()));
+        assert_eq!(composite_inventory.inventory(), inventory.inventory());
+        assert_eq!(composite_power.nodes(), power.nodes());
+        assert_eq!(composite_power.nodes()[1].draw, composites[1].power() as u64);
+
+        let mut empty = composite::Chassis::new("Empty", 1, 10, 9);
+        empty.add(Box::new(composite::Chassis::new("Inner", 2, 20, 19))).unwrap();
+        let mut inventory = InventoryVisitor::new();
+        assert_eq!(accept_composite(&empty, &mut inventory), Ok(Control::Continue));
+        assert_eq!(inventory.inventory().quantity(&Item::new(Kind::Chassis, "Inner")), 1);
+
+        // Removes the floppy disks of the chassis, which is kept in the composite tree.
+        struct Stripper;
+
+        impl EquipmentVisitor for Stripper {
+            fn enter_chassis(&mut self, chassis: &mut Chassis, _depth: usize) -> Control {
+                while chassis.remove("Floppy").is_ok() {}
+                Control::Continue
+            }
+        }
+
+        let mut inventory = InventoryVisitor::new();
+        assert_eq!(accept_composite_mut(&mut composites[1], &mut inventory), Ok(Control::Continue));
+        assert_eq!(inventory.inventory().quantity(&Item::new(Kind::FloppyDisk, "Floppy")), 3);
+        assert_eq!((composites[1].parts().count(), composites[1].power(), composites[1].net_price()), (3, 39, 390));
+        assert_eq!(accept_composite_mut(&mut composites[1], &mut Stripper), Ok(Control::Continue));
+        assert_eq!((composites[1].parts().count(), composites[1].power(), composites[1].net_price()), (1, 30, 300));
+        assert_eq!(composites[1].parts().next().unwrap().parts().count(), 0);
+
+        // Reports its totals as if it had no parts.
+        struct Inconsistent(Vec<Box<dyn CompositeEquipment>>);
+
+        impl CompositeEquipment for Inconsistent {
+            fn name(&self) -> &str {
+                "Inconsistent"
+            }
+
+            fn power(&self) -> Watt {
+                0
+            }
+
+            fn net_price(&self) -> Currency {
+                0
+            }
+
+            fn discount_price(&self) -> Currency {
+                0
+            }
+
+            fn parts(&self) -> Box<dyn Iterator<Item = &Box<dyn CompositeEquipment + '_>> + '_> {
+                Box::new(self.0.iter())
+            }
+
+            fn is_composite(&self) -> bool {
+                true
+            }
+        }
+
+        let inconsistent = Inconsistent(vec![Box::new(composite::FloppyDisk::new("Floppy", Watt::MAX, 0, 0))]);
+        assert_eq!(accept_composite(&inconsistent, &mut PricingVisitor::new()), Err("the totals of a composite chassis are less than those of its parts"));
+    }
+
+    #[test]
+    fn visitor_exam1_with_hooks() {
+        struct ChassisPricer {
+            stack: Vec<Currency>,
+            totals: Vec<Currency>,
+            max_depth: usize,
+        }
+
+        impl EquipmentVisit for ChassisPricer {
+            fn visit_floppy_disk(&mut self, floppy_disk: &FloppyDisk) -> Control {
+                *self.stack.last_mut().unwrap() += floppy_disk.net_price();
+                Control::Continue
+            }
+
+            fn visit_chassis(&mut self, chassis: &Chassis) -> Control {
+                *self.stack.last_mut().unwrap() += chassis.discount_price();
+                Control::Continue
+            }
+
+            fn enter_chassis(&mut self, _chassis: &Chassis, depth: usize) -> Control {
+                if depth > self.max_depth {
+                    return Control::SkipChildren;
+                }
+                self.stack.push(0);
+                Control::Continue
+            }
+
+            fn leave_chassis(&mut self, _chassis: &Chassis, depth: usize) {
+                if depth <= self.max_depth {
+                    self.totals.push(self.stack.pop().unwrap());
+                }
+            }
+        }
+
+        let mut chassis1 = Chassis::new("Chassis 1", 20, 200, 190);
+        chassis1.add(Box::new(FloppyDisk::new("Floppy", 4, 40, 39))).unwrap();
+        let mut chassis2 = Chassis::new("Chassis 2", 10, 100, 90);
+        chassis2.add(Box::new(FloppyDisk::new("Floppy", 2, 20, 19))).unwrap();
+        chassis2.add(Box::new(chassis1)).unwrap();
+
+        let mut v = ChassisPricer { stack: Vec::new(), totals: Vec::new(), max_depth: 1 };
+        assert_eq!(chassis2.accept_ref(&mut v), Control::Continue);
+        assert_eq!(v.totals, vec![230, 110]);
+
+        let mut v = ChassisPricer { stack: Vec::new(), totals: Vec::new(), max_depth: 0 };
+        assert_eq!(chassis2.accept_ref(&mut v), Control::Continue);
+        assert_eq!(v.totals, vec![300]);
+    }
+
+    #[test]
+    fn visitor_exam1_with_walk() {
+        struct PowerVisitor(Watt);
+
+        impl EquipmentVisit for PowerVisitor {
+            fn visit_floppy_disk(&mut self, floppy_disk: &FloppyDisk) -> Control {
+                self.0 += floppy_disk.power();
+                Control::Continue
+            }
+        }
+
+        let mut chassis1 = Chassis::new("Chassis 1", 20, 200, 190);
+        chassis1.add(Box::new(FloppyDisk::new("Floppy", 4, 40, 39))).unwrap();
+        let mut chassis2 = Chassis::new("Chassis 2", 10, 100, 90);
+        chassis2.add(Box::new(FloppyDisk::new("Floppy", 2, 20, 19))).unwrap();
+        chassis2.add(Box::new(chassis1)).unwrap();
+
+        let mut v = PowerVisitor(0);
+        walk_chassis_ref(&mut v, &chassis2, 0);
+        assert_eq!(v.0, 6);
+    }
+
+    #[test]
+    fn visitor_exam1_with_fan_out() {
+        struct PowerVisitor(Watt);
+
+        impl EquipmentVisit for PowerVisitor {
+            fn visit_floppy_disk(&mut self, floppy_disk: &FloppyDisk) -> Control {
+                self.0 += floppy_disk.power();
+                Control::Continue
+            }
+
+            fn visit_chassis(&mut self, chassis: &Chassis) -> Control {
+                self.0 += chassis.power();
+                Control::Continue
+            }
+
+            // Counts only the top-level chassis and its direct parts.
+            fn enter_chassis(&mut self, _chassis: &Chassis, depth: usize) -> Control {
+                if depth == 0 { Control::Continue } else { Control::SkipChildren }
+            }
+        }
+
+        struct PartCounter;
+
+        impl EquipmentValueVisitor<Result<Currency>> for PartCounter {
+            fn visit_floppy_disk(&mut self, _floppy_disk: &mut FloppyDisk) -> Result<Currency> {
+                Ok(1)
+            }
+
+            fn visit_chassis(&mut self, _chassis: &mut Chassis, parts: Vec<Result<Currency>>) -> Result<Currency> {
+                Ok(parts.into_iter().sum::<Result<Currency>>()? + 1)
+            }
+        }
+
+        let mut chassis1 = Chassis::new("Chassis 1", 20, 200, 190);
+        chassis1.add(Box::new(FloppyDisk::new("Floppy", 4, 40, 39))).unwrap();
+        let mut chassis2 = Chassis::new("Chassis 2", 10, 100, 90);
+        chassis2.add(Box::new(FloppyDisk::new("Floppy", 2, 20, 19))).unwrap();
+        chassis2.add(Box::new(chassis1)).unwrap();
+
+        let mut pricing = PricingVisitor::new();
+        let mut inventory = InventoryVisitor::new();
+        let mut power = PowerVisitor(0);
+        let mut fan_out: FanOut<dyn EquipmentVisit> = FanOut::new(vec![&mut pricing, &mut inventory, &mut power]);
+        assert_eq!(chassis2.accept_ref(&mut fan_out), Control::Continue);
+        assert_eq!(pricing.total_price(), Ok(340));
+        assert_eq!(inventory.inventory().list(), &BTreeMap::from([
+            (Item::new(Kind::FloppyDisk, "Floppy"), 2),
+            (Item::new(Kind::Chassis, "Chassis 1"), 1),
+            (Item::new(Kind::Chassis, "Chassis 2"), 1),
+        ]));
+        assert_eq!(power.0, 32);
+
+        let mut pricing = PricingVisitor::new();
+        let mut fan_out: FanOut<dyn EquipmentVisitor> = FanOut::new(vec![&mut pricing]);
+        chassis2.accept(&mut fan_out);
+        assert_eq!(pricing.total_price(), Ok(340));
+
+        let mut equipment: Box<dyn Equipment> = Box::new(chassis2);
+        let mut price = PriceCalculator;
+        let mut counter = PartCounter;
+        let mut fan_out: FanOut<dyn EquipmentValueVisitor<Result<Currency>>> = FanOut::new(vec![&mut price, &mut counter]);
+        assert_eq!(equipment.accept_value(&mut fan_out), vec![Ok(340), Ok(4)]);
+    }
+
+    #[test]
+    fn visitor_exam1_with_pricing_policy() {
+        let mut chassis1 = Chassis::new("Chassis 1", 20, 200, 190);
+        chassis1.add(Box::new(FloppyDisk::new("Floppy", 4, 40, 39))).unwrap();
+        chassis1.add(Box::new(FloppyDisk::new("Floppy", 4, 40, 39))).unwrap();
+        let mut chassis2 = Chassis::new("Chassis 2", 10, 100, 90);
+        chassis2.add(Box::new(FloppyDisk::new("Floppy", 2, 20, 19))).unwrap();
+        chassis2.add(Box::new(chassis1)).unwrap();
+
+        let mut v = PricingVisitor::new();
+        chassis2.accept_ref(&mut v);
+        assert_eq!(v.total_price(), Ok(380));
+
+        let policy = PricingPolicy::new()
+            .with_rule(Kind::FloppyDisk, PriceRule::Tiers(vec![(2, Rate::percent(10)), (3, Rate::percent(25))]))
+            .with_tax(Kind::FloppyDisk, Rate::percent(10))
+            .with_rule(Kind::Chassis, PriceRule::Off(Rate::basis_points(500)));
+        let mut v = PricingVisitor::with_policy(policy);
+        chassis2.accept(&mut v);
+        let quote = v.quote().unwrap();
+        let items: Vec<(&str, usize, Currency, Currency)> = quote.items.iter().map(|item| (item.name.as_str(), item.depth, item.price, item.tax)).collect();
+        assert_eq!(items, vec![("Chassis 2", 0, 95, 0), ("Floppy", 1, 15, 2), ("Chassis 1", 1, 190, 0), ("Floppy", 2, 30, 3), ("Floppy", 2, 30, 3)]);
+        assert_eq!(quote.subtotals, vec![
+            Subtotal { name: "Chassis 2".to_string(), depth: 0, price: 360, tax: 8 },
+            Subtotal { name: "Chassis 1".to_string(), depth: 1, price: 250, tax: 6 },
+        ]);
+        assert_eq!((quote.price(), quote.tax(), quote.total(), v.total_price()), (Ok(360), Ok(8), Ok(368), Ok(368)));
+        assert_eq!(quote.subtotals[0].total(), quote.total());
+
+        // Floppy disks of different SKUs count toward their own tiers.
+        let mut chassis = Chassis::new("Chassis", 0, 0, 0);
+        chassis.add(Box::new(FloppyDisk::new("Floppy", 1, 100, 0).with_sku("FD-720"))).unwrap();
+        chassis.add(Box::new(FloppyDisk::new("Floppy", 1, 100, 0).with_sku("FD-1440"))).unwrap();
+        let mut v = PricingVisitor::with_policy(PricingPolicy::new().with_rule(Kind::FloppyDisk, PriceRule::Tiers(vec![(2, Rate::percent(10))])));
+        chassis.accept_ref(&mut v);
+        assert_eq!(v.total_price(), Ok(200));
+
+        // A tax or a subtotal overflowing the currency is an error, and a rate off more than the net price makes it free.
+        let mut v = PricingVisitor::with_policy(PricingPolicy::new().with_tax(Kind::FloppyDisk, Rate::percent(10)));
+        FloppyDisk::new("Floppy", 1, Currency::MAX, 0).accept_ref(&mut v);
+        assert_eq!(v.total_price(), Err("amount overflows the currency"));
+        let mut chassis = Chassis::new("Chassis", 1, Currency::MAX, 0);
+        chassis.add(Box::new(FloppyDisk::new("Floppy", 1, Currency::MAX / 2 + 1, 0))).unwrap();
+        chassis.add(Box::new(FloppyDisk::new("Floppy", 1, Currency::MAX / 2 + 1, 0))).unwrap();
+        let mut v = PricingVisitor::new();
+        chassis.accept_ref(&mut v);
+        assert_eq!(v.quote(), Err("amount overflows the currency"));
+        let mut chassis: Box<dyn Equipment> = Box::new(chassis);
+        assert_eq!(chassis.accept_value(&mut PriceCalculator), Err("amount overflows the currency"));
+        let mut v = PricingVisitor::with_policy(PricingPolicy::new().with_rule(Kind::Chassis, PriceRule::Off(Rate::percent(u32::MAX))));
+        Chassis::new("Chassis", 1, Currency::MAX, 0).accept_ref(&mut v);
+        assert_eq!(v.total_price(), Ok(0));
+    }
+
+    #[test]
+    fn visitor_exam1_with_inventory() {
+        let mut chassis1 = Chassis::new("Chassis", 20, 200, 190).with_sku("CH-2");
+        chassis1.add(Box::new(FloppyDisk::new("Floppy", 4, 40, 39).with_sku("FD-720"))).unwrap();
+        let mut chassis2 = Chassis::new("Chassis", 10, 100, 90).with_sku("CH-1");
+        chassis2.add(Box::new(FloppyDisk::new("Floppy", 2, 20, 19).with_sku("FD-1440"))).unwrap();
+        chassis2.add(Box::new(FloppyDisk::new("Floppy", 2, 20, 19).with_sku("FD-1440"))).unwrap();
+        chassis2.add(Box::new(FloppyDisk::new("Floppy, \"Spare\"", 2, 20, 19))).unwrap();
+        chassis2.add(Box::new(chassis1)).unwrap();
+
+        let mut v = InventoryVisitor::new();
+        chassis2.accept_ref(&mut v);
+        let mut stock = v.inventory().clone();
+        let (fd720, fd1440) = (Item::new(Kind::FloppyDisk, "Floppy").with_sku("FD-720"), Item::new(Kind::FloppyDisk, "Floppy").with_sku("FD-1440"));
+        let items: Vec<(&Item, &Quantity)> = stock.list().iter().collect();
+        assert_eq!(items, vec![
+            (&fd1440, &2),
+            (&fd720, &1),
+            (&Item::new(Kind::FloppyDisk, "Floppy, \"Spare\""), &1),
+            (&Item::new(Kind::Chassis, "Chassis").with_sku("CH-1"), &1),
+            (&Item::new(Kind::Chassis, "Chassis").with_sku("CH-2"), &1),
+        ]);
+
+        stock.merge(v.inventory());
+        assert_eq!((stock.quantity(&fd1440), stock.quantity(&Item::new(Kind::FloppyDisk, "Floppy"))), (4, 0));
+        let mut order = Inventory::new();
+        order.add(fd1440.clone(), 3);
+        order.add(fd720.clone(), 2);
+        stock.subtract(&order).unwrap();
+        assert_eq!((stock.quantity(&fd1440), stock.list().contains_key(&fd720)), (1, false));
+        assert!(stock.subtract(&order).is_err());
+        assert_eq!(stock.quantity(&fd1440), 1);
+
+        let mut thresholds = Inventory::new();
+        thresholds.add(fd1440.clone(), 2);
+        thresholds.add(fd720.clone(), 1);
+        thresholds.add(Item::new(Kind::Chassis, "Chassis").with_sku("CH-1"), 1);
+        let reorders = stock.reorders(&thresholds);
+        assert_eq!(reorders, vec![
+            Reorder { item: fd1440.clone(), stock: 1, threshold: 2 },
+            Reorder { item: fd720.clone(), stock: 0, threshold: 1 },
+        ]);
+        assert_eq!(reorders[0].shortfall(), 1);
+        assert_eq!(Reorder { item: fd720.clone(), stock: 3, threshold: 1 }.shortfall(), 0);
+
+        let mut inventory = Inventory::new();
+        assert_eq!((inventory.to_csv(), inventory.to_json()), ("kind,name,sku,quantity\n".to_string(), "[]".to_string()));
+        inventory.add(fd1440, 2);
+        inventory.add(Item::new(Kind::Chassis, "Chassis, \"Big\""), 1);
+        assert_eq!(inventory.to_csv(), "kind,name,sku,quantity\nfloppy_disk,Floppy,FD-1440,2\nchassis,\"Chassis, \"\"Big\"\"\",,1\n");
+        let mut skus = Inventory::new();
+        skus.add(Item::new(Kind::FloppyDisk, "Floppy"), 1);
+        skus.add(Item::new(Kind::FloppyDisk, "Floppy").with_sku(""), 2);
+        assert_eq!(skus.to_csv(), "kind,name,sku,quantity\nfloppy_disk,Floppy,,1\nfloppy_disk,Floppy,\"\",2\n");
+        assert_eq!(inventory.to_json(), concat!(
+            "[\n",
+            r#"  {"kind": "floppy_disk", "name": "Floppy", "sku": "FD-1440", "quantity": 2},"#, "\n",
+            r#"  {"kind": "chassis", "name": "Chassis, \"Big\"", "sku": null, "quantity": 1}"#, "\n",
+            "]",
+        ));
+    }
+
+    #[test]
+    fn visitor_exam1_with_power_visitor() {
+        let mut chassis1 = Chassis::new("Chassis 1", 20, 200, 190);
+        chassis1.add(Box::new(FloppyDisk::new("Floppy", 4, 40, 39))).unwrap();
+        chassis1.add(Box::new(FloppyDisk::new("Floppy", 6, 60, 59))).unwrap();
+        let mut chassis2 = Chassis::new("Chassis 2", 10, 100, 90);
+        chassis2.add(Box::new(FloppyDisk::new("Floppy", 2, 20, 19))).unwrap();
+        chassis2.add(Box::new(chassis1)).unwrap();
+        let mut equipments: Vec<Box<dyn Equipment>> = vec![Box::new(chassis2), Box::new(FloppyDisk::new("Floppy", 1, 10, 9))];
+
+        let mut v = PowerVisitor::new().with_default_supply(40).with_supply(&[0, 1], 25).with_dissipation(0.5).unwrap();
+        for e in equipments.iter() {
+            e.accept_ref(&mut v);
+        }
+        let nodes: Vec<(&str, usize, Watt, u64, Option<Watt>)> = v.nodes().iter().map(|node| (node.name.as_str(), node.depth(), node.power, node.draw, node.supply)).collect();
+        assert_eq!(nodes, vec![
+            ("Chassis 2", 0, 10, 42, Some(40)),
+            ("Floppy", 1, 2, 2, None),
+            ("Chassis 1", 1, 20, 30, Some(25)),
+            ("Floppy", 2, 4, 4, None),
+            ("Floppy", 2, 6, 6, None),
+            ("Floppy", 0, 1, 1, None),
+        ]);
+        let overloaded: Vec<&str> = v.overloaded().map(|node| node.name.as_str()).collect();
+        assert_eq!(overloaded, vec!["Chassis 2", "Chassis 1"]);
+        assert_eq!((v.total_draw(), v.total_heat(), v.nodes()[2].heat), (43, 21.5, 15.0));
+        assert!((v.nodes()[0].heat_btu_per_hour() - 71.66).abs() < 0.01);
+
+        let mut v = PowerVisitor::new().with_supply(&[0], 50);
+        equipments[0].accept(&mut v);
+        assert_eq!((v.total_draw(), v.total_heat(), v.overloaded().count()), (42, 42.0, 0));
+        assert_eq!(v.nodes()[4].path, vec![0, 1, 1]);
+
+        // The trees visited by a visitor have their own paths.
+        let mut v = PowerVisitor::new().with_supply(&[0], 50).with_supply(&[1], 40);
+        equipments[0].accept(&mut v);
+        equipments[0].accept(&mut v);
+        let overloaded: Vec<&[usize]> = v.overloaded().map(|node| node.path.as_slice()).collect();
+        assert_eq!(overloaded, vec![&[1][..]]);
+
+        // Chassis of the same name have their own supplies, and draws add up beyond the range of `Watt`.
+        let mut rack = Chassis::new("Rack", 0, 0, 0);
+        rack.add(Box::new(Chassis::new("Shelf", Watt::MAX, 0, 0))).unwrap();
+        rack.add(Box::new(Chassis::new("Shelf", Watt::MAX, 0, 0))).unwrap();
+        let mut v = PowerVisitor::new().with_supply(&[0, 0], Watt::MAX).with_supply(&[0, 1], 0);
+        rack.accept_ref(&mut v);
+        let overloaded: Vec<&[usize]> = v.overloaded().map(|node| node.path.as_slice()).collect();
+        assert_eq!(overloaded, vec![&[0, 1][..]]);
+        assert_eq!(v.total_draw(), 2 * Watt::MAX as u64);
+        assert_eq!(PowerVisitor::new().with_dissipation(1.5).err(), Some("a dissipation must be within [0, 1]"));
+    }
+
+    #[test]
+    fn visitor_exam1_with_accept_all() {
+        // Fails for a chassis without floppy disks.
+        struct EmptyChassis;
+
+        impl TryEquipmentVisit<String> for EmptyChassis {
+            fn visit_chassis(&mut self, chassis: &Chassis) -> result::Result<(), String> {
+                if chassis.parts().next().is_none() { Err("no parts".to_string()) } else { Ok(()) }
+            }
+        }
+
+        let mut chassis = Chassis::new("Chassis", 10, 100, 90);
+        chassis.add(Box::new(FloppyDisk::new("Floppy", 4, 40, 39))).unwrap();
+        let mut equipments: Vec<Box<dyn Equipment>> = vec![
+            Box::new(FloppyDisk::new("Floppy", 2, 20, 19)),
+            Box::new(chassis),
+            Box::new(Chassis::new("Empty", 5, 50, 45)),
+        ];
+
+        assert_eq!(equipments.iter().visit_with(PricingVisitor::new()).total_price(), Ok(195));
+        let v = equipments.iter_mut().visit_with::<_, ByVisitor>(InventoryVisitor::new());
+        assert_eq!(v.inventory().quantity(&Item::new(Kind::FloppyDisk, "Floppy")), 2);
+        assert_eq!(equipments.iter_mut().accept_each(&mut PriceCalculator), vec![Ok(20), Ok(130), Ok(45)]);
+
+        let results = equipments.iter().accept_each(&mut EmptyChassis);
+        assert_eq!(results[..2], [Ok(Control::Continue), Ok(Control::Continue)]);
+        assert_eq!(results[2].as_ref().unwrap_err().to_string(), "Empty: no parts");
+    }
+
+    #[test]
+    fn visitor_exam1_with_try_visitor() {
+        // Fails for an equipment discounted below half of its net price, or nested deeper than `max_depth`.
+        struct PriceValidator {
+            visited: Vec<String>,
+            max_depth: usize,
+        }
+
+        impl PriceValidator {
+            fn validate(&mut self, name: &str, net_price: Currency, discount_price: Currency) -> result::Result<(), String> {
+                self.visited.push(name.to_string());
+                if discount_price * 2 < net_price { Err(format!("discount price {discount_price} is too low")) } else { Ok(()) }
+            }
+        }
+
+        impl TryEquipmentVisit<String> for PriceValidator {
+            fn visit_floppy_disk(&mut self, floppy_disk: &FloppyDisk) -> result::Result<(), String> {
+                self.validate(floppy_disk.name(), floppy_disk.net_price(), floppy_disk.discount_price())
+            }
+
+            fn visit_chassis(&mut self, chassis: &Chassis) -> result::Result<(), String> {
+                self.validate(chassis.name(), chassis.net_price(), chassis.discount_price())
+            }
+
+            fn enter_chassis(&mut self, _chassis: &Chassis, depth: usize) -> result::Result<Control, String> {
+                if depth > self.max_depth { Err(format!("depth {depth} is too deep")) } else { Ok(Control::Continue) }
+            }
+        }
+
+        impl TryEquipmentVisitor<String> for PriceValidator {
+            fn visit_floppy_disk(&mut self, floppy_disk: &mut FloppyDisk) -> result::Result<(), String> {
+                self.validate(floppy_disk.name(), floppy_disk.net_price(), floppy_disk.discount_price())
+            }
+        }
+
+        // Counts the times the traversal reaches it, whether or not the visitor is called.
+        struct Probe(Rc<Cell<usize>>);
+
+        impl Equipment for Probe {
+            fn name(&self) -> &str {
+                "Probe"
+            }
+
+            fn power(&self) -> Watt {
+                0
+            }
+
+            fn net_price(&self) -> Currency {
+                0
+            }
+
+            fn discount_price(&self) -> Currency {
+                0
+            }
+        }
+
+        impl Element for Probe {
+            fn accept_at(&mut self, _visitor: &mut dyn EquipmentVisitor, _depth: usize) -> Control {
+                self.0.set(self.0.get() + 1);
+                Control::Continue
+            }
+
+            fn accept_ref_at(&self, _visitor: &mut dyn EquipmentVisit, _depth: usize) -> Control {
+                self.0.set(self.0.get() + 1);
+                Control::Continue
+            }
+        }
+
+        let reached = Rc::new(Cell::new(0));
+        let mut chassis1 = Chassis::new("Chassis 1", 20, 200, 190);
+        chassis1.add(Box::new(FloppyDisk::new("Floppy", 4, 40, 39))).unwrap();
+        chassis1.add(Box::new(FloppyDisk::new("Cheap", 5, 50, 20))).unwrap();
+        chassis1.add(Box::new(FloppyDisk::new("Unseen", 6, 60, 10))).unwrap();
+        chassis1.add(Box::new(Probe(Rc::clone(&reached)))).unwrap();
+        let mut chassis2 = Chassis::new("Chassis 2", 10, 100, 90);
+        chassis2.add(Box::new(FloppyDisk::new("Floppy", 2, 20, 19))).unwrap();
+        chassis2.add(Box::new(chassis1)).unwrap();
+        chassis2.add(Box::new(Probe(Rc::clone(&reached)))).unwrap();
+        chassis2.add(Box::new(Chassis::new("Chassis 3", 30, 300, 290))).unwrap();
+        let mut equipment: Box<dyn Equipment> = Box::new(chassis2);
+
+        let mut v = PriceValidator { visited: Vec::new(), max_depth: 1 };
+        let error = equipment.try_accept_ref(&mut v).unwrap_err();
+        assert_eq!(error.path, vec!["Chassis 2", "Chassis 1", "Cheap"]);
+        assert_eq!(error.to_string(), "Chassis 2/Chassis 1/Cheap: discount price 20 is too low");
+        assert_eq!(v.visited, vec!["Floppy", "Floppy", "Cheap"]);
+        assert_eq!(reached.get(), 0);
+
+        let mut v = PriceValidator { visited: Vec::new(), max_depth: 0 };
+        let error = equipment.try_accept_ref(&mut v).unwrap_err();
+        assert_eq!((error.path, error.error), (vec!["Chassis 2".to_string(), "Chassis 1".to_string()], "depth 1 is too deep".to_string()));
+        assert_eq!(v.visited, vec!["Floppy"]);
+
+        let mut v = PriceValidator { visited: Vec::new(), max_depth: 0 };
+        let mut floppy: Box<dyn Equipment> = Box::new(FloppyDisk::new("Floppy", 1, 10, 9));
+        assert_eq!(floppy.try_accept_ref(&mut v), Ok(Control::Continue));
+        assert_eq!(floppy.try_accept(&mut v), Ok(Control::Continue));
+        let error = equipment.try_accept(&mut v).unwrap_err();
+        assert_eq!(error.path, vec!["Chassis 2", "Chassis 1", "Cheap"]);
+        assert_eq!(reached.get(), 0);
+
+        // A chassis which fails stops the traversal, and the error keeps its path.
+        let mut bad = Chassis::new("Bad", 10, 100, 10);
+        bad.add(Box::new(FloppyDisk::new("Inner", 1, 10, 9))).unwrap();
+        let mut root = Chassis::new("Root", 10, 100, 90);
+        root.add(Box::new(bad)).unwrap();
+        root.add(Box::new(FloppyDisk::new("After", 1, 10, 9))).unwrap();
+        root.add(Box::new(FloppyDisk::new("After2", 1, 10, 9))).unwrap();
+        root.add(Box::new(Probe(Rc::clone(&reached)))).unwrap();
+        let root: Box<dyn Equipment> = Box::new(root);
+        let mut v = PriceValidator { visited: Vec::new(), max_depth: 1 };
+        let error = root.try_accept_ref(&mut v).unwrap_err();
+        assert_eq!(error.to_string(), "Root/Bad: discount price 10 is too low");
+        assert_eq!(v.visited, vec!["Inner", "Bad"]);
+        assert_eq!(reached.get(), 0);
+    }
+}
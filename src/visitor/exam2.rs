@@ -1,131 +1,1240 @@
+use std::cmp::Ordering;
 use std::fmt::Debug;
-use dyn_ord::DynEq;
+use std::iter::{Product, Sum};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+use dyn_ord::{DynEq, DynOrd};
+use crate::utils::num;
+use crate::utils::num::arith::{self, Shifter, Value};
+use crate::utils::num::literal::{self, Literal, ParseListError, ParseLiteralError};
+use crate::utils::stats::{Fold, Histogram, ParFold, Summary};
+use gof_derive::{visitor, Visitable};
+use crate::visitor::{Accept, ByValue, ByVisitor, Control, FanOut, Merge, par_visit, par_visit_mut};
 
-pub trait Number: Debug + DynEq {
+pub use crate::utils::num::arith::{Operator, Overflow};
+
+// Generates the trait `Element` accepting the visitors, and the traits `Visitor` and `Visit`.
+visitor! {
+    accept = Element, visit_mut = Visitor, control = Control;
+    Int,
+    Float,
+    BigInt,
+    Rational,
+    Decimal,
+    Complex,
+}
+
+pub trait Number: Debug + DynOrd + Element {
     fn as_dyn_eq(&self) -> &dyn DynEq;
+    fn as_dyn_ord(&self) -> &dyn DynOrd;
     fn as_f64(&self) -> f64;
-    fn accept(&mut self, visitor: &mut dyn Visitor);
+    fn fold(self: Box<Self>, folder: &mut dyn Folder) -> Box<dyn Number>;
+}
+
+impl PartialEq for dyn Number + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.dyn_eq(other.as_dyn_eq())
+    }
+}
+
+// Numbers of the same kind are compared by `DynOrd`, and numbers of different kinds by value.
+// Equal values of different kinds are ordered by rank, `Int` before `Float`, to be consistent with `PartialEq`.
+// `NaN` and `Complex` which is not real are not comparable with any other number.
+impl PartialOrd for dyn Number + '_ {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.dyn_ord(other.as_dyn_ord()).or_else(|| match self.accept_binary(other, &mut Comparator)? {
+            Ordering::Equal => Some(Operand::of(self).rank().cmp(&Operand::of(other).rank())),
+            ordering => Some(ordering),
+        })
+    }
+}
+
+impl PartialEq for dyn Number + Send + Sync + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        (self as &dyn Number) == (other as &dyn Number)
+    }
+}
+
+impl PartialOrd for dyn Number + Send + Sync + '_ {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self as &dyn Number).partial_cmp(other as &dyn Number)
+    }
 }
 
-impl PartialEq for dyn Number + '_ {
-    fn eq(&self, other: &Self) -> bool {
-        self.dyn_eq(other.as_dyn_eq())
+#[derive(Debug, Clone, PartialEq, PartialOrd, Visitable)]
+pub struct Int(pub i32);
+
+impl Number for Int {
+    fn as_dyn_eq(&self) -> &dyn DynEq {
+        self
+    }
+
+    fn as_dyn_ord(&self) -> &dyn DynOrd {
+        self
+    }
+
+    fn as_f64(&self) -> f64 {
+        self.0 as f64
+    }
+
+    fn fold(self: Box<Self>, folder: &mut dyn Folder) -> Box<dyn Number> {
+        folder.fold_int(*self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Visitable)]
+pub struct Float(pub f64);
+
+impl Number for Float {
+    fn as_dyn_eq(&self) -> &dyn DynEq {
+        self
+    }
+
+    fn as_dyn_ord(&self) -> &dyn DynOrd {
+        self
+    }
+
+    fn as_f64(&self) -> f64 {
+        self.0
+    }
+
+    fn fold(self: Box<Self>, folder: &mut dyn Folder) -> Box<dyn Number> {
+        folder.fold_float(*self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Visitable)]
+pub struct BigInt(pub num::BigInt);
+
+impl Number for BigInt {
+    fn as_dyn_eq(&self) -> &dyn DynEq {
+        self
+    }
+
+    fn as_dyn_ord(&self) -> &dyn DynOrd {
+        self
+    }
+
+    fn as_f64(&self) -> f64 {
+        self.0.to_f64()
+    }
+
+    fn fold(self: Box<Self>, folder: &mut dyn Folder) -> Box<dyn Number> {
+        folder.fold_big_int(*self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Visitable)]
+pub struct Rational(pub num::Rational);
+
+impl Number for Rational {
+    fn as_dyn_eq(&self) -> &dyn DynEq {
+        self
+    }
+
+    fn as_dyn_ord(&self) -> &dyn DynOrd {
+        self
+    }
+
+    fn as_f64(&self) -> f64 {
+        self.0.to_f64()
+    }
+
+    fn fold(self: Box<Self>, folder: &mut dyn Folder) -> Box<dyn Number> {
+        folder.fold_rational(*self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Visitable)]
+pub struct Decimal(pub num::Decimal);
+
+impl Number for Decimal {
+    fn as_dyn_eq(&self) -> &dyn DynEq {
+        self
+    }
+
+    fn as_dyn_ord(&self) -> &dyn DynOrd {
+        self
+    }
+
+    fn as_f64(&self) -> f64 {
+        self.0.to_f64()
+    }
+
+    fn fold(self: Box<Self>, folder: &mut dyn Folder) -> Box<dyn Number> {
+        folder.fold_decimal(*self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Visitable)]
+pub struct Complex(pub num::Complex);
+
+impl Number for Complex {
+    fn as_dyn_eq(&self) -> &dyn DynEq {
+        self
+    }
+
+    fn as_dyn_ord(&self) -> &dyn DynOrd {
+        self
+    }
+
+    // Returns `NaN` if the number is not real.
+    fn as_f64(&self) -> f64 {
+        if self.0.is_real() { self.0.re } else { f64::NAN }
+    }
+
+    fn fold(self: Box<Self>, folder: &mut dyn Folder) -> Box<dyn Number> {
+        folder.fold_complex(*self)
+    }
+}
+
+// NOTE: `Folder` consumes a number and produces a possibly different kind of number,
+//       which cannot be done in place by a `Visitor`.
+pub trait Folder {
+    fn fold_int(&mut self, int: Int) -> Box<dyn Number> {
+        Box::new(int)
+    }
+
+    fn fold_float(&mut self, float: Float) -> Box<dyn Number> {
+        Box::new(float)
+    }
+
+    fn fold_big_int(&mut self, big_int: BigInt) -> Box<dyn Number> {
+        Box::new(big_int)
+    }
+
+    fn fold_rational(&mut self, rational: Rational) -> Box<dyn Number> {
+        Box::new(rational)
+    }
+
+    fn fold_decimal(&mut self, decimal: Decimal) -> Box<dyn Number> {
+        Box::new(decimal)
+    }
+
+    fn fold_complex(&mut self, complex: Complex) -> Box<dyn Number> {
+        Box::new(complex)
+    }
+}
+
+pub trait ValueVisitor<R> {
+    fn visit_int(&mut self, int: &mut Int) -> R;
+    fn visit_float(&mut self, float: &mut Float) -> R;
+    fn visit_big_int(&mut self, big_int: &mut BigInt) -> R;
+    fn visit_rational(&mut self, rational: &mut Rational) -> R;
+    fn visit_decimal(&mut self, decimal: &mut Decimal) -> R;
+    fn visit_complex(&mut self, complex: &mut Complex) -> R;
+}
+
+impl dyn Number + '_ {
+    pub fn accept_value<R>(&mut self, visitor: &mut dyn ValueVisitor<R>) -> R {
+        let mut adapter = ValueAdapter { visitor, result: None };
+        self.accept(&mut adapter);
+        adapter.result.expect("an accepted number must produce a result")
+    }
+}
+
+struct ValueAdapter<'a, R> {
+    visitor: &'a mut dyn ValueVisitor<R>,
+    result: Option<R>,
+}
+
+impl<R> Visitor for ValueAdapter<'_, R> {
+    fn visit_int(&mut self, int: &mut Int) {
+        self.result = Some(self.visitor.visit_int(int));
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        self.result = Some(self.visitor.visit_float(float));
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        self.result = Some(self.visitor.visit_big_int(big_int));
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        self.result = Some(self.visitor.visit_rational(rational));
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        self.result = Some(self.visitor.visit_decimal(decimal));
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        self.result = Some(self.visitor.visit_complex(complex));
+    }
+}
+
+// NOTE: `BinaryVisitor` dispatches on the kinds of both operands.
+//       Operands of different kinds are promoted to the higher rank first,
+//       so only the pairs of the same kind need to be visited.
+pub trait BinaryVisitor<R> {
+    fn visit_int_int(&mut self, lhs: &Int, rhs: &Int) -> R;
+    fn visit_float_float(&mut self, lhs: &Float, rhs: &Float) -> R;
+    fn visit_big_int_big_int(&mut self, lhs: &BigInt, rhs: &BigInt) -> R;
+    fn visit_rational_rational(&mut self, lhs: &Rational, rhs: &Rational) -> R;
+    fn visit_decimal_decimal(&mut self, lhs: &Decimal, rhs: &Decimal) -> R;
+    fn visit_complex_complex(&mut self, lhs: &Complex, rhs: &Complex) -> R;
+}
+
+impl dyn Number + '_ {
+    // Dispatches on each operand by accepting an `Operand` visitor, promotes both to the same kind,
+    // and then dispatches on the pair.
+    pub fn accept_binary<R>(&self, rhs: &dyn Number, visitor: &mut dyn BinaryVisitor<R>) -> R {
+        let (lhs, rhs) = (Operand::of(self), Operand::of(rhs));
+        let rank = lhs.rank().max(rhs.rank());
+        match (lhs.promote_to(rank), rhs.promote_to(rank)) {
+            (Operand::Int(lhs), Operand::Int(rhs)) => visitor.visit_int_int(&lhs, &rhs),
+            (Operand::Float(lhs), Operand::Float(rhs)) => visitor.visit_float_float(&lhs, &rhs),
+            (Operand::BigInt(lhs), Operand::BigInt(rhs)) => visitor.visit_big_int_big_int(&lhs, &rhs),
+            (Operand::Rational(lhs), Operand::Rational(rhs)) => visitor.visit_rational_rational(&lhs, &rhs),
+            (Operand::Decimal(lhs), Operand::Decimal(rhs)) => visitor.visit_decimal_decimal(&lhs, &rhs),
+            (Operand::Complex(lhs), Operand::Complex(rhs)) => visitor.visit_complex_complex(&lhs, &rhs),
+            _ => unreachable!("operands promoted to the same rank are of the same kind"),
+        }
+    }
+}
+
+// A copy of a number of a known kind, which can be promoted to another kind.
+enum Operand {
+    Int(Int),
+    Float(Float),
+    BigInt(BigInt),
+    Rational(Rational),
+    Decimal(Decimal),
+    Complex(Complex),
+}
+
+impl Operand {
+    fn of(number: &dyn Number) -> Operand {
+        let mut operand = None;
+        number.accept_ref(&mut operand);
+        operand.expect("an accepted number must produce an operand")
+    }
+
+    // Ranks the kinds of numbers in the order of promotion:
+    // `Int` < `BigInt` < `Decimal` < `Rational` < `Float` < `Complex`.
+    fn rank(&self) -> u8 {
+        match self {
+            Operand::Int(_) => 0,
+            Operand::BigInt(_) => 1,
+            Operand::Decimal(_) => 2,
+            Operand::Rational(_) => 3,
+            Operand::Float(_) => 4,
+            Operand::Complex(_) => 5,
+        }
+    }
+
+    // Promotes the operand to the kind of the next rank. `Complex` is not promoted.
+    fn promote(self) -> Operand {
+        match self {
+            Operand::Int(int) => Operand::BigInt(BigInt(num::BigInt::from(int.0))),
+            Operand::BigInt(big_int) => Operand::Decimal(Decimal(num::Decimal::from(big_int.0))),
+            Operand::Decimal(decimal) => Operand::Rational(Rational(decimal.0.to_rational())),
+            Operand::Rational(rational) => Operand::Float(Float(rational.0.to_f64())),
+            Operand::Float(float) => Operand::Complex(Complex(num::Complex::from(float.0))),
+            Operand::Complex(complex) => Operand::Complex(complex),
+        }
+    }
+
+    fn promote_to(mut self, rank: u8) -> Operand {
+        while self.rank() < rank {
+            self = self.promote();
+        }
+        self
+    }
+
+}
+
+impl Visit for Option<Operand> {
+    fn visit_int(&mut self, int: &Int) {
+        *self = Some(Operand::Int(int.clone()));
+    }
+
+    fn visit_float(&mut self, float: &Float) {
+        *self = Some(Operand::Float(float.clone()));
+    }
+
+    fn visit_big_int(&mut self, big_int: &BigInt) {
+        *self = Some(Operand::BigInt(big_int.clone()));
+    }
+
+    fn visit_rational(&mut self, rational: &Rational) {
+        *self = Some(Operand::Rational(rational.clone()));
+    }
+
+    fn visit_decimal(&mut self, decimal: &Decimal) {
+        *self = Some(Operand::Decimal(decimal.clone()));
+    }
+
+    fn visit_complex(&mut self, complex: &Complex) {
+        *self = Some(Operand::Complex(complex.clone()));
+    }
+}
+
+impl<V: Visitor> Accept<V, ByVisitor> for &mut Box<dyn Number + '_> {
+    type Output = ();
+
+    fn accept_with(self, visitor: &mut V) {
+        self.accept(visitor);
+    }
+}
+
+impl<V: Visit> Accept<V, ByVisitor> for &Box<dyn Number + '_> {
+    type Output = ();
+
+    fn accept_with(self, visitor: &mut V) {
+        self.accept_ref(visitor);
+    }
+}
+
+impl<V: Folder> Accept<V, ByVisitor> for Box<dyn Number> {
+    type Output = Box<dyn Number>;
+
+    fn accept_with(self, visitor: &mut V) -> Box<dyn Number> {
+        self.fold(visitor)
+    }
+}
+
+impl<R, V: ValueVisitor<R>> Accept<V, ByValue<R>> for &mut Box<dyn Number + '_> {
+    type Output = R;
+
+    fn accept_with(self, visitor: &mut V) -> R {
+        self.accept_value(visitor)
+    }
+}
+
+// Accepts a binary visitor by a pair of numbers, such as the items of `lhs.iter().zip(rhs.iter())`.
+impl<R, V: BinaryVisitor<R>> Accept<V, ByValue<R>> for (&Box<dyn Number + '_>, &Box<dyn Number + '_>) {
+    type Output = R;
+
+    fn accept_with(self, visitor: &mut V) -> R {
+        self.0.accept_binary(self.1.as_ref(), visitor)
+    }
+}
+
+// NOTE: A `Folder` consumes the number, so it cannot be fanned out to several folders.
+impl Visitor for FanOut<'_, dyn Visitor + '_> {
+    fn visit_int(&mut self, int: &mut Int) {
+        self.leaf(|visitor| visitor.visit_int(int));
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        self.leaf(|visitor| visitor.visit_float(float));
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        self.leaf(|visitor| visitor.visit_big_int(big_int));
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        self.leaf(|visitor| visitor.visit_rational(rational));
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        self.leaf(|visitor| visitor.visit_decimal(decimal));
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        self.leaf(|visitor| visitor.visit_complex(complex));
+    }
+}
+
+impl Visit for FanOut<'_, dyn Visit + '_> {
+    fn visit_int(&mut self, int: &Int) {
+        self.leaf(|visitor| visitor.visit_int(int));
+    }
+
+    fn visit_float(&mut self, float: &Float) {
+        self.leaf(|visitor| visitor.visit_float(float));
+    }
+
+    fn visit_big_int(&mut self, big_int: &BigInt) {
+        self.leaf(|visitor| visitor.visit_big_int(big_int));
+    }
+
+    fn visit_rational(&mut self, rational: &Rational) {
+        self.leaf(|visitor| visitor.visit_rational(rational));
+    }
+
+    fn visit_decimal(&mut self, decimal: &Decimal) {
+        self.leaf(|visitor| visitor.visit_decimal(decimal));
+    }
+
+    fn visit_complex(&mut self, complex: &Complex) {
+        self.leaf(|visitor| visitor.visit_complex(complex));
+    }
+}
+
+impl<R> ValueVisitor<Vec<R>> for FanOut<'_, dyn ValueVisitor<R> + '_> {
+    fn visit_int(&mut self, int: &mut Int) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_int(int))
+    }
+
+    fn visit_float(&mut self, float: &mut Float) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_float(float))
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_big_int(big_int))
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_rational(rational))
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_decimal(decimal))
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_complex(complex))
+    }
+}
+
+impl<R> BinaryVisitor<Vec<R>> for FanOut<'_, dyn BinaryVisitor<R> + '_> {
+    fn visit_int_int(&mut self, lhs: &Int, rhs: &Int) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_int_int(lhs, rhs))
+    }
+
+    fn visit_float_float(&mut self, lhs: &Float, rhs: &Float) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_float_float(lhs, rhs))
+    }
+
+    fn visit_big_int_big_int(&mut self, lhs: &BigInt, rhs: &BigInt) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_big_int_big_int(lhs, rhs))
+    }
+
+    fn visit_rational_rational(&mut self, lhs: &Rational, rhs: &Rational) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_rational_rational(lhs, rhs))
+    }
+
+    fn visit_decimal_decimal(&mut self, lhs: &Decimal, rhs: &Decimal) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_decimal_decimal(lhs, rhs))
+    }
+
+    fn visit_complex_complex(&mut self, lhs: &Complex, rhs: &Complex) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_complex_complex(lhs, rhs))
+    }
+}
+
+pub type OverflowError = arith::OverflowError<Box<dyn Number + Send + Sync>>;
+
+// Shifts the numbers by `arith::Shifter`, which documents the overflow policies.
+pub struct Increaser {
+    shifter: Shifter<Box<dyn Number + Send + Sync>>,
+}
+
+impl Increaser {
+    pub fn new(step: u16) -> Self {
+        Self::with_overflow(step as f64, Overflow::Checked)
+    }
+
+    pub fn with_overflow(step: f64, overflow: Overflow) -> Self {
+        Increaser {
+            shifter: Shifter::new(step, overflow),
+        }
+    }
+
+    pub fn errors(&self) -> &[OverflowError] {
+        self.shifter.errors()
+    }
+}
+
+impl Visitor for Increaser {
+    fn visit_int(&mut self, int: &mut Int) {
+        if let Some(Value::Int(shifted)) = self.shifter.shift_in_place(Value::Int(int.0)) {
+            int.0 = shifted;
+        }
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        if let Some(Value::Float(shifted)) = self.shifter.shift_in_place(Value::Float(float.0)) {
+            float.0 = shifted;
+        }
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        if let Some(Value::BigInt(shifted)) = self.shifter.shift_in_place(Value::BigInt(big_int.0.clone())) {
+            big_int.0 = shifted;
+        }
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        if let Some(Value::Rational(shifted)) = self.shifter.shift_in_place(Value::Rational(rational.0.clone())) {
+            rational.0 = shifted;
+        }
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        if let Some(Value::Decimal(shifted)) = self.shifter.shift_in_place(Value::Decimal(decimal.0.clone())) {
+            decimal.0 = shifted;
+        }
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        if let Some(Value::Complex(shifted)) = self.shifter.shift_in_place(Value::Complex(complex.0)) {
+            complex.0 = shifted;
+        }
+    }
+}
+
+impl Merge for Increaser {
+    fn split(&self) -> Self {
+        Increaser {
+            shifter: self.shifter.split(),
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.shifter.merge(other.shifter);
+    }
+}
+
+impl Folder for Increaser {
+    fn fold_int(&mut self, int: Int) -> Box<dyn Number> {
+        self.shifter.shift(Value::Int(int.0)).into()
+    }
+
+    fn fold_float(&mut self, float: Float) -> Box<dyn Number> {
+        self.shifter.shift(Value::Float(float.0)).into()
+    }
+
+    fn fold_big_int(&mut self, big_int: BigInt) -> Box<dyn Number> {
+        self.shifter.shift(Value::BigInt(big_int.0)).into()
+    }
+
+    fn fold_rational(&mut self, rational: Rational) -> Box<dyn Number> {
+        self.shifter.shift(Value::Rational(rational.0)).into()
+    }
+
+    fn fold_decimal(&mut self, decimal: Decimal) -> Box<dyn Number> {
+        self.shifter.shift(Value::Decimal(decimal.0)).into()
+    }
+
+    fn fold_complex(&mut self, complex: Complex) -> Box<dyn Number> {
+        self.shifter.shift(Value::Complex(complex.0)).into()
+    }
+}
+
+// NOTE: `Decreaser` is an `Increaser` by the negated step, so the errors report the negated step.
+pub struct Decreaser {
+    increaser: Increaser,
+}
+
+impl Decreaser {
+    pub fn new(step: u16) -> Self {
+        Self::with_overflow(step as f64, Overflow::Checked)
+    }
+
+    pub fn with_overflow(step: f64, overflow: Overflow) -> Self {
+        Decreaser {
+            increaser: Increaser::with_overflow(-step, overflow),
+        }
+    }
+
+    pub fn errors(&self) -> &[OverflowError] {
+        self.increaser.errors()
+    }
+}
+
+impl Visitor for Decreaser {
+    fn visit_int(&mut self, int: &mut Int) {
+        self.increaser.visit_int(int);
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        self.increaser.visit_float(float);
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        self.increaser.visit_big_int(big_int);
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        self.increaser.visit_rational(rational);
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        self.increaser.visit_decimal(decimal);
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        self.increaser.visit_complex(complex);
+    }
+}
+
+impl Merge for Decreaser {
+    fn split(&self) -> Self {
+        Decreaser {
+            increaser: self.increaser.split(),
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.increaser.merge(other.increaser);
+    }
+}
+
+impl Folder for Decreaser {
+    fn fold_int(&mut self, int: Int) -> Box<dyn Number> {
+        self.increaser.fold_int(int)
+    }
+
+    fn fold_float(&mut self, float: Float) -> Box<dyn Number> {
+        self.increaser.fold_float(float)
+    }
+
+    fn fold_big_int(&mut self, big_int: BigInt) -> Box<dyn Number> {
+        self.increaser.fold_big_int(big_int)
+    }
+
+    fn fold_rational(&mut self, rational: Rational) -> Box<dyn Number> {
+        self.increaser.fold_rational(rational)
+    }
+
+    fn fold_decimal(&mut self, decimal: Decimal) -> Box<dyn Number> {
+        self.increaser.fold_decimal(decimal)
+    }
+
+    fn fold_complex(&mut self, complex: Complex) -> Box<dyn Number> {
+        self.increaser.fold_complex(complex)
+    }
+}
+
+pub struct Rounder;
+
+impl Folder for Rounder {
+    fn fold_float(&mut self, float: Float) -> Box<dyn Number> {
+        arith::round(float.0).into()
+    }
+}
+
+impl From<Value> for Box<dyn Number + Send + Sync> {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Int(int) => Box::new(Int(int)),
+            Value::Float(float) => Box::new(Float(float)),
+            Value::BigInt(big_int) => Box::new(BigInt(big_int)),
+            Value::Rational(rational) => Box::new(Rational(rational)),
+            Value::Decimal(decimal) => Box::new(Decimal(decimal)),
+            Value::Complex(complex) => Box::new(Complex(complex)),
+        }
+    }
+}
+
+impl From<Value> for Box<dyn Number> {
+    fn from(value: Value) -> Self {
+        let number: Box<dyn Number + Send + Sync> = value.into();
+        number
+    }
+}
+
+// Calculates `lhs <operator> rhs` by the kernels of `arith`, such as `arith::calculate_int`,
+// which document the promotion rules.
+pub struct Calculator {
+    operator: Operator,
+}
+
+impl Calculator {
+    pub fn new(operator: Operator) -> Self {
+        Calculator { operator }
+    }
+}
+
+impl BinaryVisitor<Box<dyn Number>> for Calculator {
+    fn visit_int_int(&mut self, lhs: &Int, rhs: &Int) -> Box<dyn Number> {
+        arith::calculate_int(self.operator, lhs.0, rhs.0).into()
+    }
+
+    fn visit_float_float(&mut self, lhs: &Float, rhs: &Float) -> Box<dyn Number> {
+        arith::calculate_float(self.operator, lhs.0, rhs.0).into()
+    }
+
+    fn visit_big_int_big_int(&mut self, lhs: &BigInt, rhs: &BigInt) -> Box<dyn Number> {
+        arith::calculate_big_int(self.operator, &lhs.0, &rhs.0).into()
+    }
+
+    fn visit_rational_rational(&mut self, lhs: &Rational, rhs: &Rational) -> Box<dyn Number> {
+        arith::calculate_rational(self.operator, &lhs.0, &rhs.0).into()
+    }
+
+    fn visit_decimal_decimal(&mut self, lhs: &Decimal, rhs: &Decimal) -> Box<dyn Number> {
+        arith::calculate_decimal(self.operator, &lhs.0, &rhs.0).into()
+    }
+
+    fn visit_complex_complex(&mut self, lhs: &Complex, rhs: &Complex) -> Box<dyn Number> {
+        arith::calculate_complex(self.operator, lhs.0, rhs.0).into()
+    }
+}
+
+// Compares `lhs` with `rhs` by value. The exact kinds are compared exactly with each other,
+// but compared with `Float` after being converted to `f64`. `NaN` and `Complex` which is not real
+// are not comparable.
+pub struct Comparator;
+
+impl BinaryVisitor<Option<Ordering>> for Comparator {
+    fn visit_int_int(&mut self, lhs: &Int, rhs: &Int) -> Option<Ordering> {
+        Some(lhs.0.cmp(&rhs.0))
+    }
+
+    fn visit_float_float(&mut self, lhs: &Float, rhs: &Float) -> Option<Ordering> {
+        lhs.0.partial_cmp(&rhs.0)
+    }
+
+    fn visit_big_int_big_int(&mut self, lhs: &BigInt, rhs: &BigInt) -> Option<Ordering> {
+        Some(lhs.0.cmp(&rhs.0))
+    }
+
+    fn visit_rational_rational(&mut self, lhs: &Rational, rhs: &Rational) -> Option<Ordering> {
+        Some(lhs.0.cmp(&rhs.0))
+    }
+
+    fn visit_decimal_decimal(&mut self, lhs: &Decimal, rhs: &Decimal) -> Option<Ordering> {
+        Some(lhs.0.cmp(&rhs.0))
+    }
+
+    fn visit_complex_complex(&mut self, lhs: &Complex, rhs: &Complex) -> Option<Ordering> {
+        lhs.0.partial_cmp(&rhs.0)
+    }
+}
+
+pub struct Accumulator<'a, T> {
+    fold: Fold<'a, T, dyn Number>,
+}
+
+impl<'a, T> Accumulator<'a, T> {
+    pub fn new<F>(init: T, op: F) -> Self
+    where
+        F: Fn(&mut T, &dyn Number) + 'a,
+    {
+        Accumulator {
+            fold: Fold::new(init, op),
+        }
+    }
+
+    pub fn result(&self) -> &T {
+        self.fold.result()
+    }
+}
+
+impl<'a, T> Visitor for Accumulator<'a, T> {
+    fn visit_int(&mut self, int: &mut Int) {
+        self.fold.push(int);
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        self.fold.push(float);
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        self.fold.push(big_int);
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        self.fold.push(rational);
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        self.fold.push(decimal);
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        self.fold.push(complex);
+    }
+}
+
+impl<'a, T> Visit for Accumulator<'a, T> {
+    fn visit_int(&mut self, int: &Int) {
+        self.fold.push(int);
+    }
+
+    fn visit_float(&mut self, float: &Float) {
+        self.fold.push(float);
+    }
+
+    fn visit_big_int(&mut self, big_int: &BigInt) {
+        self.fold.push(big_int);
+    }
+
+    fn visit_rational(&mut self, rational: &Rational) {
+        self.fold.push(rational);
+    }
+
+    fn visit_decimal(&mut self, decimal: &Decimal) {
+        self.fold.push(decimal);
+    }
+
+    fn visit_complex(&mut self, complex: &Complex) {
+        self.fold.push(complex);
+    }
+}
+
+// An `Accumulator` which can be split and merged for `par_accept` and `par_accept_ref`. See `stats::ParFold`.
+pub struct ParAccumulator<'a, T> {
+    fold: ParFold<'a, T, dyn Number>,
+}
+
+impl<'a, T: Clone> ParAccumulator<'a, T> {
+    pub fn new<F, M>(identity: T, op: F, merge: M) -> Self
+    where
+        F: Fn(&mut T, &dyn Number) + Send + Sync + 'a,
+        M: Fn(&mut T, T) + Send + Sync + 'a,
+    {
+        ParAccumulator {
+            fold: ParFold::new(identity, op, merge),
+        }
+    }
+
+    pub fn result(&self) -> &T {
+        self.fold.result()
+    }
+}
+
+impl<T> Visitor for ParAccumulator<'_, T> {
+    fn visit_int(&mut self, int: &mut Int) {
+        self.fold.push(int);
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        self.fold.push(float);
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        self.fold.push(big_int);
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        self.fold.push(rational);
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        self.fold.push(decimal);
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        self.fold.push(complex);
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct Int(pub i32);
+impl<T> Visit for ParAccumulator<'_, T> {
+    fn visit_int(&mut self, int: &Int) {
+        self.fold.push(int);
+    }
 
-impl Number for Int {
-    fn as_dyn_eq(&self) -> &dyn DynEq {
-        self
+    fn visit_float(&mut self, float: &Float) {
+        self.fold.push(float);
     }
 
-    fn as_f64(&self) -> f64 {
-        self.0 as f64
+    fn visit_big_int(&mut self, big_int: &BigInt) {
+        self.fold.push(big_int);
+    }
+
+    fn visit_rational(&mut self, rational: &Rational) {
+        self.fold.push(rational);
+    }
+
+    fn visit_decimal(&mut self, decimal: &Decimal) {
+        self.fold.push(decimal);
     }
 
-    fn accept(&mut self, visitor: &mut dyn Visitor) {
-        visitor.visit_int(self);
+    fn visit_complex(&mut self, complex: &Complex) {
+        self.fold.push(complex);
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct Float(pub f64);
+impl<T: Clone> Merge for ParAccumulator<'_, T> {
+    fn split(&self) -> Self {
+        ParAccumulator {
+            fold: self.fold.split(),
+        }
+    }
 
-impl Number for Float {
-    fn as_dyn_eq(&self) -> &dyn DynEq {
-        self
+    fn merge(&mut self, other: Self) {
+        self.fold.merge(other.fold);
     }
+}
 
-    fn as_f64(&self) -> f64 {
-        self.0
+// Computes the statistics of `stats::Summary`, where the min and max are compared as `dyn Number`.
+pub struct Statistics {
+    summary: Summary<Box<dyn Number + Send + Sync>>,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Statistics {
+            summary: Summary::new(),
+        }
+    }
+
+    // Keeps the values to compute the median and quantiles.
+    pub fn with_quantiles(self) -> Self {
+        Statistics {
+            summary: self.summary.with_quantiles(),
+        }
+    }
+
+    pub fn with_histogram(self, lo: f64, hi: f64, bins: usize) -> Self {
+        Statistics {
+            summary: self.summary.with_histogram(lo, hi, bins),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.summary.count()
+    }
+
+    pub fn sum(&self) -> Box<dyn Number> {
+        self.summary.sum().into()
+    }
+
+    pub fn min(&self) -> Option<&dyn Number> {
+        self.summary.min().map(|min| min.as_ref() as &dyn Number)
+    }
+
+    pub fn max(&self) -> Option<&dyn Number> {
+        self.summary.max().map(|max| max.as_ref() as &dyn Number)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        self.summary.mean()
+    }
+
+    pub fn variance(&self) -> Option<f64> {
+        self.summary.variance()
+    }
+
+    pub fn sample_variance(&self) -> Option<f64> {
+        self.summary.sample_variance()
     }
 
-    fn accept(&mut self, visitor: &mut dyn Visitor) {
-        visitor.visit_float(self);
+    pub fn stddev(&self) -> Option<f64> {
+        self.summary.stddev()
+    }
+
+    pub fn sample_stddev(&self) -> Option<f64> {
+        self.summary.sample_stddev()
+    }
+
+    // Returns `None` if there are no values or the quantiles are not enabled by `with_quantiles`.
+    pub fn median(&self) -> Option<f64> {
+        self.summary.median()
+    }
+
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        self.summary.quantile(q)
+    }
+
+    pub fn histogram(&self) -> Option<&Histogram> {
+        self.summary.histogram()
     }
 }
 
-pub trait Visitor {
-    fn visit_int(&mut self, int: &mut Int);
-    fn visit_float(&mut self, float: &mut Float);
+impl Default for Statistics {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-pub struct Increaser {
-    step: u16,
+impl Merge for Statistics {
+    fn split(&self) -> Self {
+        Statistics {
+            summary: self.summary.split(),
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.summary.merge(other.summary);
+    }
 }
 
-impl Increaser {
-    pub fn new(step: u16) -> Self {
-        Increaser { step }
+impl Visit for Statistics {
+    fn visit_int(&mut self, int: &Int) {
+        self.summary.push(Value::Int(int.0), || Box::new(int.clone()));
+    }
+
+    fn visit_float(&mut self, float: &Float) {
+        self.summary.push(Value::Float(float.0), || Box::new(float.clone()));
+    }
+
+    fn visit_big_int(&mut self, big_int: &BigInt) {
+        self.summary.push(Value::BigInt(big_int.0.clone()), || Box::new(big_int.clone()));
+    }
+
+    fn visit_rational(&mut self, rational: &Rational) {
+        self.summary.push(Value::Rational(rational.0.clone()), || Box::new(rational.clone()));
+    }
+
+    fn visit_decimal(&mut self, decimal: &Decimal) {
+        self.summary.push(Value::Decimal(decimal.0.clone()), || Box::new(decimal.clone()));
+    }
+
+    fn visit_complex(&mut self, complex: &Complex) {
+        self.summary.push(Value::Complex(complex.0), || Box::new(complex.clone()));
     }
 }
 
-impl Visitor for Increaser {
+impl Visitor for Statistics {
     fn visit_int(&mut self, int: &mut Int) {
-        int.0 += self.step as i32;
+        Visit::visit_int(self, int);
     }
 
     fn visit_float(&mut self, float: &mut Float) {
-        float.0 += self.step as f64;
+        Visit::visit_float(self, float);
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        Visit::visit_big_int(self, big_int);
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        Visit::visit_rational(self, rational);
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        Visit::visit_decimal(self, decimal);
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        Visit::visit_complex(self, complex);
     }
 }
 
-pub struct Decreaser {
-    step: u16,
+// Visits the numbers on `threads` threads, each visiting a contiguous chunk by a split of `visitor`,
+// and merges the splits into `visitor` in the order of the chunks. See `Merge`.
+// NOTE: The numbers are shared with or sent to the threads, so they must be `Sync` or `Send`.
+pub fn par_accept<V: Visitor + Merge + Send>(numbers: &mut [Box<dyn Number + Send>], visitor: &mut V, threads: usize) {
+    par_visit_mut(numbers, visitor, threads, |number, visitor| {
+        number.accept(visitor);
+    });
 }
 
-impl Decreaser {
-    pub fn new(step: u16) -> Self {
-        Decreaser { step }
+pub fn par_accept_ref<V: Visit + Merge + Send>(numbers: &[Box<dyn Number + Sync>], visitor: &mut V, threads: usize) {
+    par_visit(numbers, visitor, threads, |number, visitor| {
+        number.accept_ref(visitor);
+    });
+}
+
+// Parses an integer as `Int`, or as `BigInt` if it does not fit in `i32`, and any other literal as `Float`.
+// See `Literal` for the accepted forms, such as `0xFF`, `1_000` and `1.5e-3`.
+impl FromStr for Box<dyn Number> {
+    type Err = ParseLiteralError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse()? {
+            Literal::Integer(integer) => match integer.to_i32() {
+                Some(int) => Box::new(Int(int)),
+                None => Box::new(BigInt(integer)),
+            },
+            Literal::Float(float) => Box::new(Float(float)),
+        })
     }
 }
 
-impl Visitor for Decreaser {
-    fn visit_int(&mut self, int: &mut Int) {
-        int.0 -= self.step as i32;
+// Parses a list of numbers separated by commas and/or whitespace, such as `1, 2.5 0x10`.
+pub fn parse_numbers(s: &str) -> Result<Vec<Box<dyn Number>>, ParseListError<ParseLiteralError>> {
+    literal::parse_list(s)
+}
+
+// The arithmetic operators follow the promotion rules of `Calculator`,
+// so `Int` overflow produces `BigInt`, inexact `Int` division produces `Rational`, and division by `Int(0)` produces `Float`.
+// `Float` follows IEEE 754, so `NaN` propagates through every operator.
+impl Add for Box<dyn Number> {
+    type Output = Box<dyn Number>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.accept_binary(rhs.as_ref(), &mut Calculator::new(Operator::Add))
     }
+}
 
-    fn visit_float(&mut self, float: &mut Float) {
-        float.0 -= self.step as f64;
+impl Sub for Box<dyn Number> {
+    type Output = Box<dyn Number>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.accept_binary(rhs.as_ref(), &mut Calculator::new(Operator::Sub))
     }
 }
 
-pub struct Accumulator<'a, T> {
-    acc: T,
-    op: Box<dyn Fn(&mut T, &dyn Number) + 'a>,
+impl Mul for Box<dyn Number> {
+    type Output = Box<dyn Number>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.accept_binary(rhs.as_ref(), &mut Calculator::new(Operator::Mul))
+    }
 }
 
-impl<'a, T> Accumulator<'a, T> {
-    pub fn new<F>(init: T, op: F) -> Self
-    where
-        F: Fn(&mut T, &dyn Number) + 'a,
-    {
-        Accumulator {
-            acc: init,
-            op: Box::new(op),
-        }
+impl Div for Box<dyn Number> {
+    type Output = Box<dyn Number>;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        self.accept_binary(rhs.as_ref(), &mut Calculator::new(Operator::Div))
     }
+}
 
-    pub fn result(&self) -> &T {
-        &self.acc
+impl Neg for Box<dyn Number> {
+    type Output = Box<dyn Number>;
+
+    fn neg(self) -> Self::Output {
+        self.fold(&mut Negator)
     }
 }
 
-impl<'a, T> Visitor for Accumulator<'a, T> {
-    fn visit_int(&mut self, int: &mut Int) {
-        (self.op)(&mut self.acc, int);
+// The sum of no numbers is `Int(0)` and the product of no numbers is `Int(1)`.
+impl Sum for Box<dyn Number> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Box::new(Int(0)), |acc, n| acc + n)
     }
+}
 
-    fn visit_float(&mut self, float: &mut Float) {
-        (self.op)(&mut self.acc, float);
+impl<'a> Sum<&'a Box<dyn Number>> for Box<dyn Number> {
+    fn sum<I: Iterator<Item = &'a Box<dyn Number>>>(iter: I) -> Self {
+        iter.fold(Box::new(Int(0)), |acc: Box<dyn Number>, n| acc.accept_binary(n.as_ref(), &mut Calculator::new(Operator::Add)))
+    }
+}
+
+impl Product for Box<dyn Number> {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Box::new(Int(1)), |acc, n| acc * n)
+    }
+}
+
+impl<'a> Product<&'a Box<dyn Number>> for Box<dyn Number> {
+    fn product<I: Iterator<Item = &'a Box<dyn Number>>>(iter: I) -> Self {
+        iter.fold(Box::new(Int(1)), |acc: Box<dyn Number>, n| acc.accept_binary(n.as_ref(), &mut Calculator::new(Operator::Mul)))
+    }
+}
+
+struct Negator;
+
+impl Folder for Negator {
+    fn fold_int(&mut self, int: Int) -> Box<dyn Number> {
+        arith::negate(Value::Int(int.0)).into()
+    }
+
+    fn fold_float(&mut self, float: Float) -> Box<dyn Number> {
+        arith::negate(Value::Float(float.0)).into()
+    }
+
+    fn fold_big_int(&mut self, big_int: BigInt) -> Box<dyn Number> {
+        arith::negate(Value::BigInt(big_int.0)).into()
+    }
+
+    fn fold_rational(&mut self, rational: Rational) -> Box<dyn Number> {
+        arith::negate(Value::Rational(rational.0)).into()
+    }
+
+    fn fold_decimal(&mut self, decimal: Decimal) -> Box<dyn Number> {
+        arith::negate(Value::Decimal(decimal.0)).into()
+    }
+
+    fn fold_complex(&mut self, complex: Complex) -> Box<dyn Number> {
+        arith::negate(Value::Complex(complex.0)).into()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::visitor::AcceptAll;
 
     #[test]
     fn visitor_exam2() {
@@ -162,5 +1271,388 @@ mod tests {
             n.accept(&mut accumulator);
         }
         assert_eq!(accumulator.result(), &9.5);
+
+        let mut accumulator = Accumulator::new(0.0, |acc, n| *acc += n.as_f64());
+        for n in numbers.iter() {
+            n.accept_ref(&mut accumulator);
+        }
+        assert_eq!(accumulator.result(), &9.5);
+    }
+
+    #[test]
+    fn value_visitor_exam2() {
+        struct Truncator;
+
+        impl ValueVisitor<i64> for Truncator {
+            fn visit_int(&mut self, int: &mut Int) -> i64 {
+                int.0 as i64
+            }
+
+            fn visit_float(&mut self, float: &mut Float) -> i64 {
+                float.0 as i64
+            }
+
+            fn visit_big_int(&mut self, big_int: &mut BigInt) -> i64 {
+                big_int.0.to_i64().unwrap_or(0)
+            }
+
+            fn visit_rational(&mut self, rational: &mut Rational) -> i64 {
+                rational.0.trunc().to_i64().unwrap_or(0)
+            }
+
+            fn visit_decimal(&mut self, decimal: &mut Decimal) -> i64 {
+                decimal.0.trunc().to_i64().unwrap_or(0)
+            }
+
+            fn visit_complex(&mut self, complex: &mut Complex) -> i64 {
+                complex.0.re as i64
+            }
+        }
+
+        let mut numbers: Vec<Box<dyn Number>> = vec![
+            Box::new(Int(1)),
+            Box::new(Float(2.5)),
+            Box::new(Float(-3.5)),
+            Box::new(Rational(num::Rational::new(num::BigInt::from(-7), num::BigInt::from(2)).unwrap())),
+            Box::new(Decimal("4.75".parse().unwrap())),
+        ];
+        let truncated: Vec<i64> = numbers.iter_mut().map(|n| n.accept_value(&mut Truncator)).collect();
+        assert_eq!(truncated, vec![1, 2, -3, -3, 4]);
+    }
+
+    #[test]
+    fn folder_exam2() {
+        let numbers: Vec<Box<dyn Number>> = vec![
+            Box::new(Int(1)),
+            Box::new(Float(2.5)),
+            Box::new(Float(-3.4)),
+            Box::new(Float(1e10)),
+            Box::new(Float(f64::NAN)),
+        ];
+
+        let numbers: Vec<Box<dyn Number>> = numbers.into_iter().map(|n| n.fold(&mut Rounder)).collect();
+        assert_eq!(numbers[..4], vec![
+            Box::new(Int(1)) as Box<dyn Number>,
+            Box::new(Int(3)),
+            Box::new(Int(-3)),
+            Box::new(Float(1e10)),
+        ]);
+        assert!(numbers[4].as_f64().is_nan());
+    }
+
+    #[test]
+    fn binary_visitor_exam2() {
+        let calculate = |lhs: &dyn Number, operator, rhs: &dyn Number| {
+            lhs.accept_binary(rhs, &mut Calculator::new(operator))
+        };
+        let results: Vec<Box<dyn Number>> = vec![
+            calculate(&Int(7), Operator::Add, &Int(2)),
+            calculate(&Int(7), Operator::Sub, &Float(0.5)),
+            calculate(&Float(1.5), Operator::Mul, &Int(2)),
+            calculate(&Int(8), Operator::Div, &Int(2)),
+            calculate(&Int(7), Operator::Div, &Int(2)),
+            calculate(&Int(i32::MAX), Operator::Add, &Int(1)),
+            calculate(&Int(i32::MIN), Operator::Div, &Int(-1)),
+            calculate(&Int(1), Operator::Div, &Int(0)),
+        ];
+        let expected: Vec<Box<dyn Number>> = vec![
+            Box::new(Int(9)),
+            Box::new(Float(6.5)),
+            Box::new(Float(3.0)),
+            Box::new(Int(4)),
+            Box::new(Rational(num::Rational::new(num::BigInt::from(7), num::BigInt::from(2)).unwrap())),
+            Box::new(BigInt(num::BigInt::from(2147483648i64))),
+            Box::new(BigInt(num::BigInt::from(2147483648i64))),
+            Box::new(Float(f64::INFINITY)),
+        ];
+        assert_eq!(results, expected);
+
+        let compare = |lhs: &dyn Number, rhs: &dyn Number| lhs.accept_binary(rhs, &mut Comparator);
+        assert_eq!(compare(&Int(1), &Int(2)), Some(Ordering::Less));
+        assert_eq!(compare(&Int(2), &Float(1.5)), Some(Ordering::Greater));
+        assert_eq!(compare(&Float(2.0), &Int(2)), Some(Ordering::Equal));
+        assert_eq!(compare(&Float(f64::NAN), &Int(2)), None);
+    }
+
+    #[test]
+    fn ops_exam2() {
+        let n = |n: i32| Box::new(Int(n)) as Box<dyn Number>;
+        let f = |f: f64| Box::new(Float(f)) as Box<dyn Number>;
+
+        let results: Vec<Box<dyn Number>> = vec![
+            n(1) + n(2),
+            n(1) - f(0.5),
+            f(1.5) * n(2),
+            n(7) / n(2),
+            -n(i32::MIN),
+            -f(1.5),
+        ];
+        let seven_halves = Box::new(Rational(num::Rational::new(num::BigInt::from(7), num::BigInt::from(2)).unwrap())) as Box<dyn Number>;
+        let expected: Vec<Box<dyn Number>> = vec![n(3), f(0.5), f(3.0), seven_halves, Box::new(BigInt(num::BigInt::from(2147483648i64))), f(-1.5)];
+        assert_eq!(results, expected);
+        assert!((f(f64::NAN) + n(1)).as_f64().is_nan());
+
+        assert!(n(1) < n(2));
+        assert!(n(2) > f(1.5));
+        assert!(n(2) < f(2.0));
+        assert_eq!(f(f64::NAN).partial_cmp(&n(1)), None);
+
+        let mut numbers: Vec<Box<dyn Number>> = vec![f(2.0), n(3), f(-1.5), n(2)];
+        numbers.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(numbers, vec![f(-1.5), n(2), f(2.0), n(3)]);
+
+        let results: Vec<Box<dyn Number>> = vec![
+            numbers.iter().sum(),
+            vec![n(2), n(3)].into_iter().product(),
+            Vec::<Box<dyn Number>>::new().into_iter().sum(),
+        ];
+        assert_eq!(results, vec![f(5.5), n(6), n(0)]);
+    }
+
+    #[test]
+    fn overflow_exam2() {
+        let n = |n: i32| Box::new(Int(n)) as Box<dyn Number>;
+        let f = |f: f64| Box::new(Float(f)) as Box<dyn Number>;
+
+        let mut numbers: Vec<Box<dyn Number>> = vec![n(i32::MAX - 1), n(1), f(1.5)];
+        let mut increaser = Increaser::new(2);
+        for n in numbers.iter_mut() {
+            n.accept(&mut increaser);
+        }
+        assert_eq!(numbers, vec![n(i32::MAX - 1), n(3), f(3.5)]);
+        assert_eq!(increaser.errors(), &[OverflowError { number: Box::new(Int(i32::MAX - 1)), step: 2.0 }]);
+
+        let mut increaser = Increaser::with_overflow(2.0, Overflow::Saturating);
+        for n in numbers.iter_mut() {
+            n.accept(&mut increaser);
+        }
+        assert_eq!(numbers, vec![n(i32::MAX), n(5), f(5.5)]);
+
+        let mut decreaser = Decreaser::with_overflow(-1.0, Overflow::Wrapping);
+        for n in numbers.iter_mut() {
+            n.accept(&mut decreaser);
+        }
+        assert_eq!(numbers, vec![n(i32::MIN), n(6), f(6.5)]);
+
+        let mut decreaser = Decreaser::with_overflow(0.5, Overflow::Promote);
+        for n in numbers.iter_mut() {
+            n.accept(&mut decreaser);
+        }
+        assert_eq!(numbers, vec![n(i32::MIN), n(6), f(6.0)]);
+        assert_eq!(decreaser.errors().len(), 2);
+
+        let numbers: Vec<Box<dyn Number>> = numbers.into_iter().map(|n| n.fold(&mut decreaser)).collect();
+        assert_eq!(numbers, vec![f(i32::MIN as f64 - 0.5), f(5.5), f(5.5)]);
+        assert_eq!(decreaser.errors().len(), 2);
+    }
+
+    #[test]
+    fn tower_exam2() {
+        let n = |n: i32| Box::new(Int(n)) as Box<dyn Number>;
+        let f = |f: f64| Box::new(Float(f)) as Box<dyn Number>;
+        let b = |b: &str| Box::new(BigInt(b.parse().unwrap())) as Box<dyn Number>;
+        let r = |n: i32, d: i32| {
+            Box::new(Rational(num::Rational::new(num::BigInt::from(n), num::BigInt::from(d)).unwrap())) as Box<dyn Number>
+        };
+        let d = |d: &str| Box::new(Decimal(d.parse().unwrap())) as Box<dyn Number>;
+        let c = |re: f64, im: f64| Box::new(Complex(num::Complex::new(re, im))) as Box<dyn Number>;
+
+        let results: Vec<Box<dyn Number>> = vec![
+            b("99999999999999999999") + n(1),
+            b("10") / n(4),
+            d("0.1") + d("0.2"),
+            d("1") / n(3),
+            r(1, 3) + n(1),
+            r(1, 2) * f(3.0),
+            c(1.0, 2.0) * c(1.0, -2.0),
+            n(1) / b("0"),
+            -r(1, 2),
+        ];
+        let expected: Vec<Box<dyn Number>> = vec![
+            b("100000000000000000000"),
+            r(5, 2),
+            d("0.3"),
+            r(1, 3),
+            r(4, 3),
+            f(1.5),
+            c(5.0, 0.0),
+            f(f64::INFINITY),
+            r(-1, 2),
+        ];
+        assert_eq!(results, expected);
+
+        assert!(d("0.5") < r(2, 3));
+        assert!(b("99999999999999999999") > f(1e19));
+        assert!(d("0.5") < r(1, 2));
+        assert_eq!(c(1.0, 1.0).partial_cmp(&n(1)), None);
+        assert!(c(1.0, 1.0).as_f64().is_nan());
+
+        let mut numbers: Vec<Box<dyn Number>> = vec![b("99999999999999999999"), r(1, 3), d("0.1"), c(1.0, 1.0)];
+        let mut increaser = Increaser::with_overflow(0.5, Overflow::Checked);
+        for n in numbers.iter_mut() {
+            n.accept(&mut increaser);
+        }
+        assert_eq!(numbers, vec![b("99999999999999999999"), r(5, 6), d("0.6"), c(1.5, 1.0)]);
+        assert_eq!(increaser.errors(), &[OverflowError { number: Box::new(BigInt("99999999999999999999".parse().unwrap())), step: 0.5 }]);
+
+        let mut decreaser = Decreaser::with_overflow(0.5, Overflow::Promote);
+        let numbers: Vec<Box<dyn Number>> = numbers.into_iter().map(|n| n.fold(&mut decreaser)).collect();
+        assert_eq!(numbers, vec![d("99999999999999999998.5"), r(1, 3), d("0.1"), c(1.0, 1.0)]);
+
+        let mut accumulator = Accumulator::new(d("0"), |acc, n| *acc = acc.accept_binary(n, &mut Calculator::new(Operator::Add)));
+        for n in numbers[..3].iter() {
+            n.accept_ref(&mut accumulator);
+        }
+        assert!(accumulator.result().as_ref() == (d("99999999999999999998.6") + r(1, 3)).as_ref());
+    }
+
+    #[test]
+    fn parse_exam2() {
+        let n = |n: i32| Box::new(Int(n)) as Box<dyn Number>;
+        let f = |f: f64| Box::new(Float(f)) as Box<dyn Number>;
+
+        let numbers = parse_numbers("-0x1F, 1_000 1e3,2.0\n4294967296").unwrap();
+        let expected: Vec<Box<dyn Number>> = vec![
+            n(-31),
+            n(1000),
+            f(1000.0),
+            f(2.0),
+            Box::new(BigInt(num::BigInt::from(4294967296i64))),
+        ];
+        assert_eq!(numbers, expected);
+
+        let error = parse_numbers("1 2.5 0x1.8").unwrap_err();
+        assert_eq!((error.index, error.offset, error.item.as_str()), (2, 6, "0x1.8"));
+        assert_eq!(error.to_string(), "item 2 `0x1.8` at offset 6: invalid number literal");
+    }
+
+    #[test]
+    fn statistics_exam2() {
+        let n = |n: i32| Box::new(Int(n)) as Box<dyn Number>;
+        let f = |f: f64| Box::new(Float(f)) as Box<dyn Number>;
+        let d = |d: &str| Box::new(Decimal(d.parse().unwrap())) as Box<dyn Number>;
+
+        let numbers: Vec<Box<dyn Number>> = vec![n(i32::MAX), n(i32::MAX), n(-4), n(8)];
+        let mut statistics = Statistics::new().with_quantiles();
+        for n in numbers.iter() {
+            n.accept_ref(&mut statistics);
+        }
+        let sums: Vec<Box<dyn Number>> = vec![statistics.sum()];
+        assert_eq!(sums, vec![Box::new(BigInt(num::BigInt::from(2 * i32::MAX as i64 + 4))) as Box<dyn Number>]);
+        assert!(statistics.min() == Some(n(-4).as_ref()) && statistics.max() == Some(n(i32::MAX).as_ref()));
+        assert_eq!((statistics.count(), statistics.median()), (4, Some((8.0 + i32::MAX as f64) / 2.0)));
+
+        let mut numbers = parse_numbers("2, 4, 4, 4, 5, 5, 7, 9").unwrap();
+        numbers.push(d("0.5"));
+        numbers.push(f(f64::NAN));
+        let mut statistics = Statistics::new().with_quantiles().with_histogram(0.0, 10.0, 2);
+        for n in numbers.iter_mut() {
+            n.accept(&mut statistics);
+        }
+        assert_eq!(statistics.count(), 10);
+        assert!(statistics.sum().as_f64().is_nan());
+        assert!(statistics.min() == Some(d("0.5").as_ref()) && statistics.max() == Some(n(9).as_ref()));
+        assert_eq!(statistics.mean(), Some(40.5 / 9.0));
+        assert_eq!((statistics.median(), statistics.quantile(0.25)), (Some(4.0), Some(4.0)));
+        assert_eq!(statistics.histogram().unwrap().counts(), &[5, 4]);
+
+        let mut statistics = Statistics::new();
+        for n in parse_numbers("2, 4, 4, 4, 5, 5, 7, 9").unwrap().iter() {
+            n.accept_ref(&mut statistics);
+        }
+        let sums: Vec<Box<dyn Number>> = vec![statistics.sum(), Statistics::new().sum()];
+        assert_eq!(sums, vec![n(40), n(0)]);
+        assert_eq!((statistics.mean(), statistics.stddev(), statistics.median()), (Some(5.0), Some(2.0), None));
+
+        let mut statistics = Statistics::new();
+        for n in [f(0.25), f(0.5), d("0.125"), Box::new(Complex(num::Complex::new(1.0, 2.0)))].iter() {
+            n.accept_ref(&mut statistics);
+        }
+        let sums: Vec<Box<dyn Number>> = vec![statistics.sum()];
+        assert_eq!(sums, vec![Box::new(Complex(num::Complex::new(1.875, 2.0))) as Box<dyn Number>]);
+    }
+
+    #[test]
+    fn fan_out_exam2() {
+        let n = |n: i32| Box::new(Int(n)) as Box<dyn Number>;
+        let f = |f: f64| Box::new(Float(f)) as Box<dyn Number>;
+
+        let mut numbers: Vec<Box<dyn Number>> = vec![n(1), n(2), f(3.5)];
+        let mut increaser = Increaser::new(2);
+        let mut accumulator = Accumulator::new(0.0, |acc, n| *acc += n.as_f64());
+        let mut fan_out: FanOut<dyn Visitor> = FanOut::new(vec![&mut increaser, &mut accumulator]);
+        for n in numbers.iter_mut() {
+            n.accept(&mut fan_out);
+        }
+        assert_eq!(numbers, vec![n(3), n(4), f(5.5)]);
+        assert_eq!(accumulator.result(), &12.5);
+
+        let mut statistics = Statistics::new();
+        let mut accumulator = Accumulator::new(0, |acc, _n| *acc += 1);
+        let mut fan_out: FanOut<dyn Visit> = FanOut::new(vec![&mut statistics, &mut accumulator]);
+        for n in numbers.iter() {
+            n.accept_ref(&mut fan_out);
+        }
+        assert_eq!((statistics.mean(), accumulator.result()), (Some(12.5 / 3.0), &3));
+
+        let mut add = Calculator::new(Operator::Add);
+        let mut mul = Calculator::new(Operator::Mul);
+        let mut fan_out: FanOut<dyn BinaryVisitor<Box<dyn Number>>> = FanOut::new(vec![&mut add, &mut mul]);
+        assert_eq!(n(3).accept_binary(f(0.5).as_ref(), &mut fan_out), vec![f(3.5), f(1.5)]);
+    }
+
+    #[test]
+    fn accept_all_exam2() {
+        let n = |n: i32| Box::new(Int(n)) as Box<dyn Number>;
+        let f = |f: f64| Box::new(Float(f)) as Box<dyn Number>;
+
+        let mut numbers: Vec<Box<dyn Number>> = vec![n(1), n(2), f(3.5)];
+        numbers.iter_mut().accept_all(&mut Increaser::new(2));
+        assert_eq!(numbers, vec![n(3), n(4), f(5.5)]);
+        let statistics = numbers.iter().visit_with(Statistics::new());
+        assert_eq!((statistics.count(), statistics.mean()), (3, Some(12.5 / 3.0)));
+
+        let numbers = numbers.into_iter().accept_each(&mut Rounder);
+        assert_eq!(numbers, vec![n(3), n(4), n(6)]);
+        let rhs = [n(4), f(4.0), n(5)];
+        let orderings = numbers.iter().zip(rhs.iter()).accept_each(&mut Comparator);
+        assert_eq!(orderings, vec![Some(Ordering::Less), Some(Ordering::Equal), Some(Ordering::Greater)]);
+        let sums = numbers.iter().zip(rhs.iter()).accept_each(&mut Calculator::new(Operator::Add));
+        assert_eq!(sums, vec![n(7), f(8.0), n(11)]);
+    }
+
+    #[test]
+    fn parallel_exam2() {
+        let n = |n: i32| Box::new(Int(n)) as Box<dyn Number>;
+
+        let mut shared: Vec<Box<dyn Number + Sync>> = (1..=10_000).map(|i| Box::new(Int(i)) as Box<dyn Number + Sync>).collect();
+        shared[1234] = Box::new(Int(i32::MAX));
+        shared[5678] = Box::new(Float(0.5));
+        let sum = |threads| {
+            let mut accumulator = ParAccumulator::new(0.0, |acc, n| *acc += n.as_f64(), |acc, other| *acc += other);
+            par_accept_ref(&shared, &mut accumulator, threads);
+            *accumulator.result()
+        };
+        let expected = (1..=10_000).sum::<i64>() as f64 - 1235.0 - 5679.0 + i32::MAX as f64 + 0.5;
+        assert_eq!((sum(1), sum(4), sum(0), sum(20_000)), (expected, expected, expected, expected));
+
+        let statistics = |threads| {
+            let mut statistics = Statistics::new().with_quantiles().with_histogram(0.0, 10_000.0, 4);
+            par_accept_ref(&shared, &mut statistics, threads);
+            statistics
+        };
+        let (sequential, parallel) = (statistics(1), statistics(7));
+        assert_eq!((parallel.count(), parallel.sum().as_f64()), (sequential.count(), expected));
+        assert_eq!((parallel.min(), parallel.max()), (Some(Box::new(Float(0.5)).as_ref() as &dyn Number), Some(n(i32::MAX).as_ref())));
+        assert_eq!((parallel.median(), parallel.histogram()), (sequential.median(), sequential.histogram()));
+        assert!((parallel.mean().unwrap() - sequential.mean().unwrap()).abs() < 1e-6);
+        assert_eq!(parallel.variance(), statistics(7).variance());
+
+        let mut numbers: Vec<Box<dyn Number + Send>> = vec![Box::new(Int(1)), Box::new(Int(i32::MAX)), Box::new(Float(1.5)), Box::new(Int(i32::MAX - 1))];
+        let mut increaser = Increaser::new(1);
+        par_accept(&mut numbers, &mut increaser, 2);
+        let values: Vec<f64> = numbers.iter().map(|n| n.as_f64()).collect();
+        assert_eq!(values, vec![2.0, i32::MAX as f64, 2.5, i32::MAX as f64]);
+        assert_eq!(increaser.errors(), &[OverflowError { number: Box::new(Int(i32::MAX)), step: 1.0 }]);
     }
-}
\ No newline at end of file
+}
@@ -1,112 +1,1223 @@
-#[derive(Debug, PartialEq)]
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::{Product, Sum};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+use crate::utils::num;
+use crate::utils::num::arith::{self, Shifter, Value};
+use crate::utils::num::literal::{self, Literal, ParseListError, ParseLiteralError};
+use crate::utils::stats::{Fold, Histogram, ParFold, Summary};
+use gof_derive::{visitor, Visitable};
+use crate::visitor::{Accept, ByValue, ByVisitor, Control, FanOut, Merge, par_visit, par_visit_mut};
+
+pub use crate::utils::num::arith::{Operator, Overflow};
+
+// Generates the traits `Visitor` and `Visit`, and the trait `Element`, by which a variant accepts a visitor on its own.
+// NOTE: `Number` dispatches on its variants itself, so that it accepts the visitors which are not objects as well.
+visitor! {
+    accept = Element, visit_mut = Visitor, control = Control;
+    Int,
+    Float,
+    BigInt,
+    Rational,
+    Decimal,
+    Complex,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Number {
     Int(Int),
     Float(Float),
+    BigInt(BigInt),
+    Rational(Rational),
+    Decimal(Decimal),
+    Complex(Complex),
+}
+
+impl Number {
+    // Ranks the variants of numbers in the order of promotion:
+    // `Int` < `BigInt` < `Decimal` < `Rational` < `Float` < `Complex`.
+    fn rank(&self) -> u8 {
+        use Number::*;
+        match self {
+            Int(_) => 0,
+            BigInt(_) => 1,
+            Decimal(_) => 2,
+            Rational(_) => 3,
+            Float(_) => 4,
+            Complex(_) => 5,
+        }
+    }
+
+    // Promotes the number to the variant of the next rank. `Complex` is not promoted.
+    fn promote(self) -> Number {
+        match self {
+            Number::Int(int) => Number::BigInt(BigInt(num::BigInt::from(int.0))),
+            Number::BigInt(big_int) => Number::Decimal(Decimal(num::Decimal::from(big_int.0))),
+            Number::Decimal(decimal) => Number::Rational(Rational(decimal.0.to_rational())),
+            Number::Rational(rational) => Number::Float(Float(rational.0.to_f64())),
+            Number::Float(float) => Number::Complex(Complex(num::Complex::from(float.0))),
+            Number::Complex(complex) => Number::Complex(complex),
+        }
+    }
+
+    fn promote_to(&self, rank: u8) -> Number {
+        let mut number = self.clone();
+        while number.rank() < rank {
+            number = number.promote();
+        }
+        number
+    }
+
+    // Returns `NaN` for a `Complex` which is not real.
+    pub fn as_f64(&self) -> f64 {
+        use Number::*;
+        match self {
+            Int(int) => int.0 as f64,
+            Float(float) => float.0,
+            BigInt(big_int) => big_int.0.to_f64(),
+            Rational(rational) => rational.0.to_f64(),
+            Decimal(decimal) => decimal.0.to_f64(),
+            Complex(complex) if complex.0.is_real() => complex.0.re,
+            Complex(_) => f64::NAN,
+        }
+    }
+
+    // NOTE: The visitors may be unsized, so that a `&mut dyn Visitor`, such as the one
+    //       borrowed from a `Box<dyn Visitor>`, is accepted as well as a concrete visitor.
+    pub fn accept(&mut self, visitor: &mut (impl Visitor + ?Sized)) {
+        use Number::*;
+        match self {
+            Int(int) => visitor.visit_int(int),
+            Float(float) => visitor.visit_float(float),
+            BigInt(big_int) => visitor.visit_big_int(big_int),
+            Rational(rational) => visitor.visit_rational(rational),
+            Decimal(decimal) => visitor.visit_decimal(decimal),
+            Complex(complex) => visitor.visit_complex(complex),
+        }
+    }
+
+    pub fn accept_ref(&self, visitor: &mut (impl Visit + ?Sized)) {
+        use Number::*;
+        match self {
+            Int(int) => visitor.visit_int(int),
+            Float(float) => visitor.visit_float(float),
+            BigInt(big_int) => visitor.visit_big_int(big_int),
+            Rational(rational) => visitor.visit_rational(rational),
+            Decimal(decimal) => visitor.visit_decimal(decimal),
+            Complex(complex) => visitor.visit_complex(complex),
+        }
+    }
+
+    pub fn fold(self, folder: &mut (impl Folder + ?Sized)) -> Number {
+        use Number::*;
+        match self {
+            Int(int) => folder.fold_int(int),
+            Float(float) => folder.fold_float(float),
+            BigInt(big_int) => folder.fold_big_int(big_int),
+            Rational(rational) => folder.fold_rational(rational),
+            Decimal(decimal) => folder.fold_decimal(decimal),
+            Complex(complex) => folder.fold_complex(complex),
+        }
+    }
+
+    pub fn accept_binary<R>(&self, rhs: &Number, visitor: &mut (impl BinaryVisitor<R> + ?Sized)) -> R {
+        use Number::*;
+        let rank = self.rank().max(rhs.rank());
+        match (self.promote_to(rank), rhs.promote_to(rank)) {
+            (Int(lhs), Int(rhs)) => visitor.visit_int_int(&lhs, &rhs),
+            (Float(lhs), Float(rhs)) => visitor.visit_float_float(&lhs, &rhs),
+            (BigInt(lhs), BigInt(rhs)) => visitor.visit_big_int_big_int(&lhs, &rhs),
+            (Rational(lhs), Rational(rhs)) => visitor.visit_rational_rational(&lhs, &rhs),
+            (Decimal(lhs), Decimal(rhs)) => visitor.visit_decimal_decimal(&lhs, &rhs),
+            (Complex(lhs), Complex(rhs)) => visitor.visit_complex_complex(&lhs, &rhs),
+            _ => unreachable!("numbers promoted to the same rank are of the same variant"),
+        }
+    }
+
+    pub fn accept_value<R>(&mut self, visitor: &mut (impl ValueVisitor<R> + ?Sized)) -> R {
+        use Number::*;
+        match self {
+            Int(int) => visitor.visit_int(int),
+            Float(float) => visitor.visit_float(float),
+            BigInt(big_int) => visitor.visit_big_int(big_int),
+            Rational(rational) => visitor.visit_rational(rational),
+            Decimal(decimal) => visitor.visit_decimal(decimal),
+            Complex(complex) => visitor.visit_complex(complex),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Visitable)]
+pub struct Int(pub i32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Visitable)]
+pub struct Float(pub f64);
+
+#[derive(Debug, Clone, PartialEq, Visitable)]
+pub struct BigInt(pub num::BigInt);
+
+#[derive(Debug, Clone, PartialEq, Visitable)]
+pub struct Rational(pub num::Rational);
+
+#[derive(Debug, Clone, PartialEq, Visitable)]
+pub struct Decimal(pub num::Decimal);
+
+#[derive(Debug, Clone, Copy, PartialEq, Visitable)]
+pub struct Complex(pub num::Complex);
+
+// NOTE: `Folder` consumes a number and produces a possibly different variant of number,
+//       which cannot be done in place by a `Visitor`.
+pub trait Folder {
+    fn fold_int(&mut self, int: Int) -> Number {
+        Number::Int(int)
+    }
+
+    fn fold_float(&mut self, float: Float) -> Number {
+        Number::Float(float)
+    }
+
+    fn fold_big_int(&mut self, big_int: BigInt) -> Number {
+        Number::BigInt(big_int)
+    }
+
+    fn fold_rational(&mut self, rational: Rational) -> Number {
+        Number::Rational(rational)
+    }
+
+    fn fold_decimal(&mut self, decimal: Decimal) -> Number {
+        Number::Decimal(decimal)
+    }
+
+    fn fold_complex(&mut self, complex: Complex) -> Number {
+        Number::Complex(complex)
+    }
+}
+
+pub trait ValueVisitor<R> {
+    fn visit_int(&mut self, int: &mut Int) -> R;
+    fn visit_float(&mut self, float: &mut Float) -> R;
+    fn visit_big_int(&mut self, big_int: &mut BigInt) -> R;
+    fn visit_rational(&mut self, rational: &mut Rational) -> R;
+    fn visit_decimal(&mut self, decimal: &mut Decimal) -> R;
+    fn visit_complex(&mut self, complex: &mut Complex) -> R;
+}
+
+// NOTE: `BinaryVisitor` dispatches on the variants of both operands.
+//       Operands of different variants are promoted to the higher rank first,
+//       so only the pairs of the same variant need to be visited.
+pub trait BinaryVisitor<R> {
+    fn visit_int_int(&mut self, lhs: &Int, rhs: &Int) -> R;
+    fn visit_float_float(&mut self, lhs: &Float, rhs: &Float) -> R;
+    fn visit_big_int_big_int(&mut self, lhs: &BigInt, rhs: &BigInt) -> R;
+    fn visit_rational_rational(&mut self, lhs: &Rational, rhs: &Rational) -> R;
+    fn visit_decimal_decimal(&mut self, lhs: &Decimal, rhs: &Decimal) -> R;
+    fn visit_complex_complex(&mut self, lhs: &Complex, rhs: &Complex) -> R;
+}
+
+// A visitor borrowed mutably is a visitor, so that a `Pipeline` may borrow the visitors
+// whose results are read after running it.
+impl<V: Visitor + ?Sized> Visitor for &mut V {
+    fn visit_int(&mut self, int: &mut Int) {
+        (**self).visit_int(int);
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        (**self).visit_float(float);
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        (**self).visit_big_int(big_int);
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        (**self).visit_rational(rational);
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        (**self).visit_decimal(decimal);
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        (**self).visit_complex(complex);
+    }
+}
+
+impl<F: Folder + ?Sized> Folder for &mut F {
+    fn fold_int(&mut self, int: Int) -> Number {
+        (**self).fold_int(int)
+    }
+
+    fn fold_float(&mut self, float: Float) -> Number {
+        (**self).fold_float(float)
+    }
+
+    fn fold_big_int(&mut self, big_int: BigInt) -> Number {
+        (**self).fold_big_int(big_int)
+    }
+
+    fn fold_rational(&mut self, rational: Rational) -> Number {
+        (**self).fold_rational(rational)
+    }
+
+    fn fold_decimal(&mut self, decimal: Decimal) -> Number {
+        (**self).fold_decimal(decimal)
+    }
+
+    fn fold_complex(&mut self, complex: Complex) -> Number {
+        (**self).fold_complex(complex)
+    }
+}
+
+impl<V: Visitor> Accept<V, ByVisitor> for &mut Number {
+    type Output = ();
+
+    fn accept_with(self, visitor: &mut V) {
+        self.accept(visitor);
+    }
+}
+
+impl<V: Visit> Accept<V, ByVisitor> for &Number {
+    type Output = ();
+
+    fn accept_with(self, visitor: &mut V) {
+        self.accept_ref(visitor);
+    }
+}
+
+impl<V: Folder> Accept<V, ByVisitor> for Number {
+    type Output = Number;
+
+    fn accept_with(self, visitor: &mut V) -> Number {
+        self.fold(visitor)
+    }
+}
+
+impl<R, V: ValueVisitor<R>> Accept<V, ByValue<R>> for &mut Number {
+    type Output = R;
+
+    fn accept_with(self, visitor: &mut V) -> R {
+        self.accept_value(visitor)
+    }
+}
+
+// Accepts a binary visitor by a pair of numbers, such as the items of `lhs.iter().zip(rhs.iter())`.
+impl<R, V: BinaryVisitor<R>> Accept<V, ByValue<R>> for (&Number, &Number) {
+    type Output = R;
+
+    fn accept_with(self, visitor: &mut V) -> R {
+        self.0.accept_binary(self.1, visitor)
+    }
+}
+
+// NOTE: A `Folder` consumes the number, so it cannot be fanned out to several folders.
+impl Visitor for FanOut<'_, dyn Visitor + '_> {
+    fn visit_int(&mut self, int: &mut Int) {
+        self.leaf(|visitor| visitor.visit_int(int));
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        self.leaf(|visitor| visitor.visit_float(float));
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        self.leaf(|visitor| visitor.visit_big_int(big_int));
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        self.leaf(|visitor| visitor.visit_rational(rational));
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        self.leaf(|visitor| visitor.visit_decimal(decimal));
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        self.leaf(|visitor| visitor.visit_complex(complex));
+    }
+}
+
+impl Visit for FanOut<'_, dyn Visit + '_> {
+    fn visit_int(&mut self, int: &Int) {
+        self.leaf(|visitor| visitor.visit_int(int));
+    }
+
+    fn visit_float(&mut self, float: &Float) {
+        self.leaf(|visitor| visitor.visit_float(float));
+    }
+
+    fn visit_big_int(&mut self, big_int: &BigInt) {
+        self.leaf(|visitor| visitor.visit_big_int(big_int));
+    }
+
+    fn visit_rational(&mut self, rational: &Rational) {
+        self.leaf(|visitor| visitor.visit_rational(rational));
+    }
+
+    fn visit_decimal(&mut self, decimal: &Decimal) {
+        self.leaf(|visitor| visitor.visit_decimal(decimal));
+    }
+
+    fn visit_complex(&mut self, complex: &Complex) {
+        self.leaf(|visitor| visitor.visit_complex(complex));
+    }
+}
+
+impl<R> ValueVisitor<Vec<R>> for FanOut<'_, dyn ValueVisitor<R> + '_> {
+    fn visit_int(&mut self, int: &mut Int) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_int(int))
+    }
+
+    fn visit_float(&mut self, float: &mut Float) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_float(float))
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_big_int(big_int))
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_rational(rational))
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_decimal(decimal))
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_complex(complex))
+    }
+}
+
+impl<R> BinaryVisitor<Vec<R>> for FanOut<'_, dyn BinaryVisitor<R> + '_> {
+    fn visit_int_int(&mut self, lhs: &Int, rhs: &Int) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_int_int(lhs, rhs))
+    }
+
+    fn visit_float_float(&mut self, lhs: &Float, rhs: &Float) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_float_float(lhs, rhs))
+    }
+
+    fn visit_big_int_big_int(&mut self, lhs: &BigInt, rhs: &BigInt) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_big_int_big_int(lhs, rhs))
+    }
+
+    fn visit_rational_rational(&mut self, lhs: &Rational, rhs: &Rational) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_rational_rational(lhs, rhs))
+    }
+
+    fn visit_decimal_decimal(&mut self, lhs: &Decimal, rhs: &Decimal) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_decimal_decimal(lhs, rhs))
+    }
+
+    fn visit_complex_complex(&mut self, lhs: &Complex, rhs: &Complex) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_complex_complex(lhs, rhs))
+    }
+}
+
+pub type OverflowError = arith::OverflowError<Number>;
+
+// Shifts the numbers by `arith::Shifter`, which documents the overflow policies.
+pub struct Increaser {
+    shifter: Shifter<Number>,
+}
+
+impl Increaser {
+    pub fn new(step: u16) -> Self {
+        Self::with_overflow(step as f64, Overflow::Checked)
+    }
+
+    pub fn with_overflow(step: f64, overflow: Overflow) -> Self {
+        Increaser {
+            shifter: Shifter::new(step, overflow),
+        }
+    }
+
+    pub fn errors(&self) -> &[OverflowError] {
+        self.shifter.errors()
+    }
+}
+
+impl Visitor for Increaser {
+    fn visit_int(&mut self, int: &mut Int) {
+        if let Some(Value::Int(shifted)) = self.shifter.shift_in_place(Value::Int(int.0)) {
+            int.0 = shifted;
+        }
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        if let Some(Value::Float(shifted)) = self.shifter.shift_in_place(Value::Float(float.0)) {
+            float.0 = shifted;
+        }
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        if let Some(Value::BigInt(shifted)) = self.shifter.shift_in_place(Value::BigInt(big_int.0.clone())) {
+            big_int.0 = shifted;
+        }
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        if let Some(Value::Rational(shifted)) = self.shifter.shift_in_place(Value::Rational(rational.0.clone())) {
+            rational.0 = shifted;
+        }
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        if let Some(Value::Decimal(shifted)) = self.shifter.shift_in_place(Value::Decimal(decimal.0.clone())) {
+            decimal.0 = shifted;
+        }
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        if let Some(Value::Complex(shifted)) = self.shifter.shift_in_place(Value::Complex(complex.0)) {
+            complex.0 = shifted;
+        }
+    }
+}
+
+impl Merge for Increaser {
+    fn split(&self) -> Self {
+        Increaser {
+            shifter: self.shifter.split(),
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.shifter.merge(other.shifter);
+    }
+}
+
+impl Folder for Increaser {
+    fn fold_int(&mut self, int: Int) -> Number {
+        self.shifter.shift(Value::Int(int.0)).into()
+    }
+
+    fn fold_float(&mut self, float: Float) -> Number {
+        self.shifter.shift(Value::Float(float.0)).into()
+    }
+
+    fn fold_big_int(&mut self, big_int: BigInt) -> Number {
+        self.shifter.shift(Value::BigInt(big_int.0)).into()
+    }
+
+    fn fold_rational(&mut self, rational: Rational) -> Number {
+        self.shifter.shift(Value::Rational(rational.0)).into()
+    }
+
+    fn fold_decimal(&mut self, decimal: Decimal) -> Number {
+        self.shifter.shift(Value::Decimal(decimal.0)).into()
+    }
+
+    fn fold_complex(&mut self, complex: Complex) -> Number {
+        self.shifter.shift(Value::Complex(complex.0)).into()
+    }
+}
+
+// NOTE: `Decreaser` is an `Increaser` by the negated step, so the errors report the negated step.
+pub struct Decreaser {
+    increaser: Increaser,
+}
+
+impl Decreaser {
+    pub fn new(step: u16) -> Self {
+        Self::with_overflow(step as f64, Overflow::Checked)
+    }
+
+    pub fn with_overflow(step: f64, overflow: Overflow) -> Self {
+        Decreaser {
+            increaser: Increaser::with_overflow(-step, overflow),
+        }
+    }
+
+    pub fn errors(&self) -> &[OverflowError] {
+        self.increaser.errors()
+    }
+}
+
+impl Visitor for Decreaser {
+    fn visit_int(&mut self, int: &mut Int) {
+        self.increaser.visit_int(int);
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        self.increaser.visit_float(float);
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        self.increaser.visit_big_int(big_int);
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        self.increaser.visit_rational(rational);
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        self.increaser.visit_decimal(decimal);
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        self.increaser.visit_complex(complex);
+    }
+}
+
+impl Merge for Decreaser {
+    fn split(&self) -> Self {
+        Decreaser {
+            increaser: self.increaser.split(),
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.increaser.merge(other.increaser);
+    }
+}
+
+impl Folder for Decreaser {
+    fn fold_int(&mut self, int: Int) -> Number {
+        self.increaser.fold_int(int)
+    }
+
+    fn fold_float(&mut self, float: Float) -> Number {
+        self.increaser.fold_float(float)
+    }
+
+    fn fold_big_int(&mut self, big_int: BigInt) -> Number {
+        self.increaser.fold_big_int(big_int)
+    }
+
+    fn fold_rational(&mut self, rational: Rational) -> Number {
+        self.increaser.fold_rational(rational)
+    }
+
+    fn fold_decimal(&mut self, decimal: Decimal) -> Number {
+        self.increaser.fold_decimal(decimal)
+    }
+
+    fn fold_complex(&mut self, complex: Complex) -> Number {
+        self.increaser.fold_complex(complex)
+    }
+}
+
+pub struct Rounder;
+
+impl Folder for Rounder {
+    fn fold_float(&mut self, float: Float) -> Number {
+        arith::round(float.0).into()
+    }
+}
+
+impl From<Value> for Number {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Int(int) => Number::Int(Int(int)),
+            Value::Float(float) => Number::Float(Float(float)),
+            Value::BigInt(big_int) => Number::BigInt(BigInt(big_int)),
+            Value::Rational(rational) => Number::Rational(Rational(rational)),
+            Value::Decimal(decimal) => Number::Decimal(Decimal(decimal)),
+            Value::Complex(complex) => Number::Complex(Complex(complex)),
+        }
+    }
+}
+
+// Calculates `lhs <operator> rhs` by the kernels of `arith`, such as `arith::calculate_int`,
+// which document the promotion rules.
+pub struct Calculator {
+    operator: Operator,
+}
+
+impl Calculator {
+    pub fn new(operator: Operator) -> Self {
+        Calculator { operator }
+    }
+}
+
+impl BinaryVisitor<Number> for Calculator {
+    fn visit_int_int(&mut self, lhs: &Int, rhs: &Int) -> Number {
+        arith::calculate_int(self.operator, lhs.0, rhs.0).into()
+    }
+
+    fn visit_float_float(&mut self, lhs: &Float, rhs: &Float) -> Number {
+        arith::calculate_float(self.operator, lhs.0, rhs.0).into()
+    }
+
+    fn visit_big_int_big_int(&mut self, lhs: &BigInt, rhs: &BigInt) -> Number {
+        arith::calculate_big_int(self.operator, &lhs.0, &rhs.0).into()
+    }
+
+    fn visit_rational_rational(&mut self, lhs: &Rational, rhs: &Rational) -> Number {
+        arith::calculate_rational(self.operator, &lhs.0, &rhs.0).into()
+    }
+
+    fn visit_decimal_decimal(&mut self, lhs: &Decimal, rhs: &Decimal) -> Number {
+        arith::calculate_decimal(self.operator, &lhs.0, &rhs.0).into()
+    }
+
+    fn visit_complex_complex(&mut self, lhs: &Complex, rhs: &Complex) -> Number {
+        arith::calculate_complex(self.operator, lhs.0, rhs.0).into()
+    }
+}
+
+// Compares `lhs` with `rhs` by value. The exact kinds are compared exactly with each other,
+// but compared with `Float` after being converted to `f64`. `NaN` and `Complex` which is not real
+// are not comparable.
+pub struct Comparator;
+
+impl BinaryVisitor<Option<Ordering>> for Comparator {
+    fn visit_int_int(&mut self, lhs: &Int, rhs: &Int) -> Option<Ordering> {
+        Some(lhs.0.cmp(&rhs.0))
+    }
+
+    fn visit_float_float(&mut self, lhs: &Float, rhs: &Float) -> Option<Ordering> {
+        lhs.0.partial_cmp(&rhs.0)
+    }
+
+    fn visit_big_int_big_int(&mut self, lhs: &BigInt, rhs: &BigInt) -> Option<Ordering> {
+        Some(lhs.0.cmp(&rhs.0))
+    }
+
+    fn visit_rational_rational(&mut self, lhs: &Rational, rhs: &Rational) -> Option<Ordering> {
+        Some(lhs.0.cmp(&rhs.0))
+    }
+
+    fn visit_decimal_decimal(&mut self, lhs: &Decimal, rhs: &Decimal) -> Option<Ordering> {
+        Some(lhs.0.cmp(&rhs.0))
+    }
+
+    fn visit_complex_complex(&mut self, lhs: &Complex, rhs: &Complex) -> Option<Ordering> {
+        lhs.0.partial_cmp(&rhs.0)
+    }
+}
+
+pub struct Accumulator<'a, T> {
+    fold: Fold<'a, T, Number>,
+}
+
+impl<'a, T> Accumulator<'a, T> {
+    pub fn new<F>(init: T, op: F) -> Self
+    where
+        F: Fn(&mut T, &Number) + 'a,
+    {
+        Accumulator {
+            fold: Fold::new(init, op),
+        }
+    }
+
+    pub fn result(&self) -> &T {
+        self.fold.result()
+    }
+}
+
+impl<'a, T> Visitor for Accumulator<'a, T> {
+    fn visit_int(&mut self, int: &mut Int) {
+        self.fold.push(&Number::Int(*int));
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        self.fold.push(&Number::Float(*float));
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        self.fold.push(&Number::BigInt(big_int.clone()));
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        self.fold.push(&Number::Rational(rational.clone()));
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        self.fold.push(&Number::Decimal(decimal.clone()));
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        self.fold.push(&Number::Complex(*complex));
+    }
+}
+
+impl<'a, T> Visit for Accumulator<'a, T> {
+    fn visit_int(&mut self, int: &Int) {
+        self.fold.push(&Number::Int(*int));
+    }
+
+    fn visit_float(&mut self, float: &Float) {
+        self.fold.push(&Number::Float(*float));
+    }
+
+    fn visit_big_int(&mut self, big_int: &BigInt) {
+        self.fold.push(&Number::BigInt(big_int.clone()));
+    }
+
+    fn visit_rational(&mut self, rational: &Rational) {
+        self.fold.push(&Number::Rational(rational.clone()));
+    }
+
+    fn visit_decimal(&mut self, decimal: &Decimal) {
+        self.fold.push(&Number::Decimal(decimal.clone()));
+    }
+
+    fn visit_complex(&mut self, complex: &Complex) {
+        self.fold.push(&Number::Complex(*complex));
+    }
+}
+
+// An `Accumulator` which can be split and merged for `par_accept` and `par_accept_ref`. See `stats::ParFold`.
+pub struct ParAccumulator<'a, T> {
+    fold: ParFold<'a, T, Number>,
+}
+
+impl<'a, T: Clone> ParAccumulator<'a, T> {
+    pub fn new<F, M>(identity: T, op: F, merge: M) -> Self
+    where
+        F: Fn(&mut T, &Number) + Send + Sync + 'a,
+        M: Fn(&mut T, T) + Send + Sync + 'a,
+    {
+        ParAccumulator {
+            fold: ParFold::new(identity, op, merge),
+        }
+    }
+
+    pub fn result(&self) -> &T {
+        self.fold.result()
+    }
+}
+
+impl<T> Visitor for ParAccumulator<'_, T> {
+    fn visit_int(&mut self, int: &mut Int) {
+        self.fold.push(&Number::Int(*int));
+    }
+
+    fn visit_float(&mut self, float: &mut Float) {
+        self.fold.push(&Number::Float(*float));
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        self.fold.push(&Number::BigInt(big_int.clone()));
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        self.fold.push(&Number::Rational(rational.clone()));
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        self.fold.push(&Number::Decimal(decimal.clone()));
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        self.fold.push(&Number::Complex(*complex));
+    }
+}
+
+impl<T> Visit for ParAccumulator<'_, T> {
+    fn visit_int(&mut self, int: &Int) {
+        self.fold.push(&Number::Int(*int));
+    }
+
+    fn visit_float(&mut self, float: &Float) {
+        self.fold.push(&Number::Float(*float));
+    }
+
+    fn visit_big_int(&mut self, big_int: &BigInt) {
+        self.fold.push(&Number::BigInt(big_int.clone()));
+    }
+
+    fn visit_rational(&mut self, rational: &Rational) {
+        self.fold.push(&Number::Rational(rational.clone()));
+    }
+
+    fn visit_decimal(&mut self, decimal: &Decimal) {
+        self.fold.push(&Number::Decimal(decimal.clone()));
+    }
+
+    fn visit_complex(&mut self, complex: &Complex) {
+        self.fold.push(&Number::Complex(*complex));
+    }
+}
+
+impl<T: Clone> Merge for ParAccumulator<'_, T> {
+    fn split(&self) -> Self {
+        ParAccumulator {
+            fold: self.fold.split(),
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.fold.merge(other.fold);
+    }
+}
+
+// Computes the statistics of `stats::Summary`, where the min and max are compared as `Number`.
+pub struct Statistics {
+    summary: Summary<Number>,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Statistics {
+            summary: Summary::new(),
+        }
+    }
+
+    // Keeps the values to compute the median and quantiles.
+    pub fn with_quantiles(self) -> Self {
+        Statistics {
+            summary: self.summary.with_quantiles(),
+        }
+    }
+
+    pub fn with_histogram(self, lo: f64, hi: f64, bins: usize) -> Self {
+        Statistics {
+            summary: self.summary.with_histogram(lo, hi, bins),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.summary.count()
+    }
+
+    pub fn sum(&self) -> Number {
+        self.summary.sum().into()
+    }
+
+    pub fn min(&self) -> Option<&Number> {
+        self.summary.min()
+    }
+
+    pub fn max(&self) -> Option<&Number> {
+        self.summary.max()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        self.summary.mean()
+    }
+
+    pub fn variance(&self) -> Option<f64> {
+        self.summary.variance()
+    }
+
+    pub fn sample_variance(&self) -> Option<f64> {
+        self.summary.sample_variance()
+    }
+
+    pub fn stddev(&self) -> Option<f64> {
+        self.summary.stddev()
+    }
+
+    pub fn sample_stddev(&self) -> Option<f64> {
+        self.summary.sample_stddev()
+    }
+
+    // Returns `None` if there are no values or the quantiles are not enabled by `with_quantiles`.
+    pub fn median(&self) -> Option<f64> {
+        self.summary.median()
+    }
+
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        self.summary.quantile(q)
+    }
+
+    pub fn histogram(&self) -> Option<&Histogram> {
+        self.summary.histogram()
+    }
 }
 
-impl Number {
-    pub fn as_f64(&self) -> f64 {
-        use Number::*;
-        match self {
-            Int(int) => int.0 as f64,
-            Float(float) => float.0,
-        }
+impl Default for Statistics {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    pub fn accept(&mut self, visitor: &mut impl Visitor) {
-        use Number::*;
-        match self {
-            Int(int) => visitor.visit_int(int),
-            Float(float) => visitor.visit_float(float),
+impl Merge for Statistics {
+    fn split(&self) -> Self {
+        Statistics {
+            summary: self.summary.split(),
         }
     }
+
+    fn merge(&mut self, other: Self) {
+        self.summary.merge(other.summary);
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Int(pub i32);
+impl Visit for Statistics {
+    fn visit_int(&mut self, int: &Int) {
+        self.summary.push(Value::Int(int.0), || Number::Int(*int));
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Float(pub f64);
+    fn visit_float(&mut self, float: &Float) {
+        self.summary.push(Value::Float(float.0), || Number::Float(*float));
+    }
 
-pub trait Visitor {
-    fn visit_int(&mut self, int: &mut Int);
-    fn visit_float(&mut self, float: &mut Float);
-}
+    fn visit_big_int(&mut self, big_int: &BigInt) {
+        self.summary.push(Value::BigInt(big_int.0.clone()), || Number::BigInt(big_int.clone()));
+    }
 
-pub struct Increaser {
-    step: u16,
-}
+    fn visit_rational(&mut self, rational: &Rational) {
+        self.summary.push(Value::Rational(rational.0.clone()), || Number::Rational(rational.clone()));
+    }
 
-impl Increaser {
-    pub fn new(step: u16) -> Self {
-        Increaser { step }
+    fn visit_decimal(&mut self, decimal: &Decimal) {
+        self.summary.push(Value::Decimal(decimal.0.clone()), || Number::Decimal(decimal.clone()));
+    }
+
+    fn visit_complex(&mut self, complex: &Complex) {
+        self.summary.push(Value::Complex(complex.0), || Number::Complex(*complex));
     }
 }
 
-impl Visitor for Increaser {
+impl Visitor for Statistics {
     fn visit_int(&mut self, int: &mut Int) {
-        int.0 += self.step as i32;
+        Visit::visit_int(self, int);
     }
 
     fn visit_float(&mut self, float: &mut Float) {
-        float.0 += self.step as f64;
+        Visit::visit_float(self, float);
+    }
+
+    fn visit_big_int(&mut self, big_int: &mut BigInt) {
+        Visit::visit_big_int(self, big_int);
+    }
+
+    fn visit_rational(&mut self, rational: &mut Rational) {
+        Visit::visit_rational(self, rational);
+    }
+
+    fn visit_decimal(&mut self, decimal: &mut Decimal) {
+        Visit::visit_decimal(self, decimal);
+    }
+
+    fn visit_complex(&mut self, complex: &mut Complex) {
+        Visit::visit_complex(self, complex);
     }
 }
 
-pub struct Decreaser {
-    step: u16,
+// Visits the numbers on `threads` threads, each visiting a contiguous chunk by a split of `visitor`,
+// and merges the splits into `visitor` in the order of the chunks. See `Merge`.
+pub fn par_accept<V: Visitor + Merge + Send>(numbers: &mut [Number], visitor: &mut V, threads: usize) {
+    par_visit_mut(numbers, visitor, threads, |number, visitor| number.accept(visitor));
 }
 
-impl Decreaser {
-    pub fn new(step: u16) -> Self {
-        Decreaser { step }
+pub fn par_accept_ref<V: Visit + Merge + Send>(numbers: &[Number], visitor: &mut V, threads: usize) {
+    par_visit(numbers, visitor, threads, |number, visitor| number.accept_ref(visitor));
+}
+
+enum Stage<'a> {
+    Visit(Box<dyn Visitor + 'a>),
+    Fold(Box<dyn Folder + 'a>),
+}
+
+// Applies a sequence of visitors and folders configured at runtime to numbers.
+// Each stage visits or folds all the numbers before the next stage starts.
+pub struct Pipeline<'a> {
+    stages: Vec<Stage<'a>>,
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn new() -> Self {
+        Pipeline { stages: Vec::new() }
+    }
+
+    pub fn with_visitor(mut self, visitor: impl Visitor + 'a) -> Self {
+        self.push(Box::new(visitor));
+        self
+    }
+
+    pub fn with_folder(mut self, folder: impl Folder + 'a) -> Self {
+        self.push_folder(Box::new(folder));
+        self
+    }
+
+    pub fn push(&mut self, visitor: Box<dyn Visitor + 'a>) {
+        self.stages.push(Stage::Visit(visitor));
+    }
+
+    pub fn push_folder(&mut self, folder: Box<dyn Folder + 'a>) {
+        self.stages.push(Stage::Fold(folder));
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn apply(&mut self, numbers: &mut Vec<Number>) {
+        for stage in self.stages.iter_mut() {
+            match stage {
+                Stage::Visit(visitor) => numbers.iter_mut().for_each(|n| n.accept(visitor.as_mut())),
+                Stage::Fold(folder) => *numbers = numbers.drain(..).map(|n| n.fold(folder.as_mut())).collect(),
+            }
+        }
     }
 }
 
-impl Visitor for Decreaser {
-    fn visit_int(&mut self, int: &mut Int) {
-        int.0 -= self.step as i32;
+impl Default for Pipeline<'_> {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    fn visit_float(&mut self, float: &mut Float) {
-        float.0 -= self.step as f64;
+impl<'a> From<Vec<Box<dyn Visitor + 'a>>> for Pipeline<'a> {
+    fn from(visitors: Vec<Box<dyn Visitor + 'a>>) -> Self {
+        Pipeline { stages: visitors.into_iter().map(Stage::Visit).collect() }
     }
 }
 
-pub struct Accumulator<'a, T> {
-    acc: T,
-    op: Box<dyn Fn(&mut T, &Number) + 'a>,
+// Parses an integer as `Int`, or as `BigInt` if it does not fit in `i32`, and any other literal as `Float`.
+// See `Literal` for the accepted forms, such as `0xFF`, `1_000` and `1.5e-3`.
+impl FromStr for Number {
+    type Err = ParseLiteralError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse()? {
+            Literal::Integer(integer) => match integer.to_i32() {
+                Some(int) => Number::Int(Int(int)),
+                None => Number::BigInt(BigInt(integer)),
+            },
+            Literal::Float(float) => Number::Float(Float(float)),
+        })
+    }
 }
 
-impl<'a, T> Accumulator<'a, T> {
-    pub fn new<F>(init: T, op: F) -> Self
-    where
-        F: Fn(&mut T, &Number) + 'a,
-    {
-        Accumulator {
-            acc: init,
-            op: Box::new(op),
+// Parses a list of numbers separated by commas and/or whitespace, such as `1, 2.5 0x10`.
+pub fn parse_numbers(s: &str) -> Result<Vec<Number>, ParseListError<ParseLiteralError>> {
+    literal::parse_list(s)
+}
+
+// Without a precision, a `Float` always has a fraction or an exponent, such as `2.0` or `1e21`,
+// so that it parses back to a `Float`. A precision, as in `{:.2}`, rounds the fraction of
+// `Float`, `Rational`, `Decimal` and `Complex`, and is ignored by the integers.
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Number::*;
+        match (self, f.precision()) {
+            (Int(int), _) => fmt::Display::fmt(&int.0, f),
+            (Float(float), Some(_)) => fmt::Display::fmt(&float.0, f),
+            (Float(float), None) => fmt::Debug::fmt(&float.0, f),
+            (BigInt(big_int), _) => fmt::Display::fmt(&big_int.0, f),
+            (Rational(rational), Some(_)) => fmt::Display::fmt(&rational.0.to_f64(), f),
+            (Rational(rational), None) => fmt::Display::fmt(&rational.0, f),
+            (Decimal(decimal), Some(precision)) => fmt::Display::fmt(&decimal.0.round(precision as u32), f),
+            (Decimal(decimal), None) => fmt::Display::fmt(&decimal.0, f),
+            (Complex(complex), _) => fmt::Display::fmt(&complex.0, f),
         }
     }
+}
 
-    pub fn result(&self) -> &T {
-        &self.acc
+// Numbers are compared by value.
+// Equal values of different variants are ordered by rank, `Int` before `Float`, to be consistent with `PartialEq`.
+// `NaN` and `Complex` which is not real are not comparable with any other number.
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.accept_binary(other, &mut Comparator)? {
+            Ordering::Equal => Some(self.rank().cmp(&other.rank())),
+            ordering => Some(ordering),
+        }
     }
 }
 
-impl<'a, T> Visitor for Accumulator<'a, T> {
-    fn visit_int(&mut self, int: &mut Int) {
-        (self.op)(&mut self.acc, &Number::Int(*int));
+// The arithmetic operators follow the promotion rules of `Calculator`,
+// so `Int` overflow produces `BigInt`, inexact `Int` division produces `Rational`, and division by `Int(0)` produces `Float`.
+// `Float` follows IEEE 754, so `NaN` propagates through every operator.
+impl Add for Number {
+    type Output = Number;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.accept_binary(&rhs, &mut Calculator::new(Operator::Add))
     }
+}
 
-    fn visit_float(&mut self, float: &mut Float) {
-        (self.op)(&mut self.acc, &Number::Float(*float));
+impl Sub for Number {
+    type Output = Number;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.accept_binary(&rhs, &mut Calculator::new(Operator::Sub))
+    }
+}
+
+impl Mul for Number {
+    type Output = Number;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.accept_binary(&rhs, &mut Calculator::new(Operator::Mul))
+    }
+}
+
+impl Div for Number {
+    type Output = Number;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        self.accept_binary(&rhs, &mut Calculator::new(Operator::Div))
+    }
+}
+
+impl Neg for Number {
+    type Output = Number;
+
+    fn neg(self) -> Self::Output {
+        self.fold(&mut Negator)
+    }
+}
+
+// The sum of no numbers is `Int(0)` and the product of no numbers is `Int(1)`.
+impl Sum for Number {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Number::Int(Int(0)), |acc, n| acc + n)
+    }
+}
+
+impl<'a> Sum<&'a Number> for Number {
+    fn sum<I: Iterator<Item = &'a Number>>(iter: I) -> Self {
+        iter.fold(Number::Int(Int(0)), |acc, n| acc.accept_binary(n, &mut Calculator::new(Operator::Add)))
+    }
+}
+
+impl Product for Number {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Number::Int(Int(1)), |acc, n| acc * n)
+    }
+}
+
+impl<'a> Product<&'a Number> for Number {
+    fn product<I: Iterator<Item = &'a Number>>(iter: I) -> Self {
+        iter.fold(Number::Int(Int(1)), |acc, n| acc.accept_binary(n, &mut Calculator::new(Operator::Mul)))
+    }
+}
+
+struct Negator;
+
+impl Folder for Negator {
+    fn fold_int(&mut self, int: Int) -> Number {
+        arith::negate(Value::Int(int.0)).into()
+    }
+
+    fn fold_float(&mut self, float: Float) -> Number {
+        arith::negate(Value::Float(float.0)).into()
+    }
+
+    fn fold_big_int(&mut self, big_int: BigInt) -> Number {
+        arith::negate(Value::BigInt(big_int.0)).into()
+    }
+
+    fn fold_rational(&mut self, rational: Rational) -> Number {
+        arith::negate(Value::Rational(rational.0)).into()
+    }
+
+    fn fold_decimal(&mut self, decimal: Decimal) -> Number {
+        arith::negate(Value::Decimal(decimal.0)).into()
+    }
+
+    fn fold_complex(&mut self, complex: Complex) -> Number {
+        arith::negate(Value::Complex(complex.0)).into()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::visitor::AcceptAll;
+    use std::thread;
 
     #[test]
     fn visitor_exam3() {
@@ -137,6 +1248,9 @@ mod tests {
             Number::Float(Float(4.5)),
         ];
         assert_eq!(numbers, expected);
+        let mut int = Int(1);
+        assert_eq!(int.accept(&mut decreaser), Control::Continue);
+        assert_eq!(int, Int(0));
 
         let mut accumulator = Accumulator::new(0.0, |acc, n| *acc += n.as_f64());
         for n in numbers.iter_mut() {
@@ -144,4 +1258,422 @@ mod tests {
         }
         assert_eq!(accumulator.result(), &9.5);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn visit_exam3() {
+        let numbers: Vec<Number> = vec![
+            Number::Int(Int(1)),
+            Number::Int(Int(2)),
+            Number::Float(Float(3.5)),
+        ];
+
+        let (ints, floats) = thread::scope(|s| {
+            let ints = s.spawn(|| {
+                let mut accumulator = Accumulator::new(0, |acc, n| if let Number::Int(int) = n { *acc += int.0 });
+                for n in numbers.iter() {
+                    n.accept_ref(&mut accumulator);
+                }
+                *accumulator.result()
+            });
+            let floats = s.spawn(|| {
+                let mut accumulator = Accumulator::new(0.0, |acc, n| if let Number::Float(float) = n { *acc += float.0 });
+                for n in numbers.iter() {
+                    n.accept_ref(&mut accumulator);
+                }
+                *accumulator.result()
+            });
+            (ints.join().unwrap(), floats.join().unwrap())
+        });
+        assert_eq!(ints, 3);
+        assert_eq!(floats, 3.5);
+    }
+
+    #[test]
+    fn value_visitor_exam3() {
+        struct Truncator;
+
+        impl ValueVisitor<i64> for Truncator {
+            fn visit_int(&mut self, int: &mut Int) -> i64 {
+                int.0 as i64
+            }
+
+            fn visit_float(&mut self, float: &mut Float) -> i64 {
+                float.0 as i64
+            }
+
+            fn visit_big_int(&mut self, big_int: &mut BigInt) -> i64 {
+                big_int.0.to_i64().unwrap_or(0)
+            }
+
+            fn visit_rational(&mut self, rational: &mut Rational) -> i64 {
+                rational.0.trunc().to_i64().unwrap_or(0)
+            }
+
+            fn visit_decimal(&mut self, decimal: &mut Decimal) -> i64 {
+                decimal.0.trunc().to_i64().unwrap_or(0)
+            }
+
+            fn visit_complex(&mut self, complex: &mut Complex) -> i64 {
+                complex.0.re as i64
+            }
+        }
+
+        let mut numbers: Vec<Number> = vec![
+            Number::Int(Int(1)),
+            Number::Float(Float(2.5)),
+            Number::Float(Float(-3.5)),
+            Number::Rational(Rational(num::Rational::new(num::BigInt::from(-7), num::BigInt::from(2)).unwrap())),
+            Number::Decimal(Decimal("4.75".parse().unwrap())),
+        ];
+        let truncated: Vec<i64> = numbers.iter_mut().map(|n| n.accept_value(&mut Truncator)).collect();
+        assert_eq!(truncated, vec![1, 2, -3, -3, 4]);
+    }
+
+    #[test]
+    fn folder_exam3() {
+        let numbers: Vec<Number> = vec![
+            Number::Int(Int(1)),
+            Number::Float(Float(2.5)),
+            Number::Float(Float(-3.4)),
+            Number::Float(Float(1e10)),
+            Number::Float(Float(f64::NAN)),
+        ];
+
+        let numbers: Vec<Number> = numbers.into_iter().map(|n| n.fold(&mut Rounder)).collect();
+        assert_eq!(numbers[..4], vec![
+            Number::Int(Int(1)),
+            Number::Int(Int(3)),
+            Number::Int(Int(-3)),
+            Number::Float(Float(1e10)),
+        ]);
+        assert!(numbers[4].as_f64().is_nan());
+    }
+
+    #[test]
+    fn binary_visitor_exam3() {
+        use Number::{Float as F, Int as I};
+
+        let calculate = |lhs: Number, operator, rhs: Number| lhs.accept_binary(&rhs, &mut Calculator::new(operator));
+        assert_eq!(calculate(I(Int(7)), Operator::Add, I(Int(2))), I(Int(9)));
+        assert_eq!(calculate(I(Int(7)), Operator::Sub, F(Float(0.5))), F(Float(6.5)));
+        assert_eq!(calculate(F(Float(1.5)), Operator::Mul, I(Int(2))), F(Float(3.0)));
+        assert_eq!(calculate(I(Int(8)), Operator::Div, I(Int(2))), I(Int(4)));
+        assert_eq!(calculate(I(Int(7)), Operator::Div, I(Int(2))), Number::Rational(Rational(num::Rational::new(num::BigInt::from(7), num::BigInt::from(2)).unwrap())));
+        assert_eq!(calculate(I(Int(i32::MAX)), Operator::Add, I(Int(1))), Number::BigInt(BigInt(num::BigInt::from(2147483648i64))));
+        assert_eq!(calculate(I(Int(i32::MIN)), Operator::Div, I(Int(-1))), Number::BigInt(BigInt(num::BigInt::from(2147483648i64))));
+        assert_eq!(calculate(I(Int(1)), Operator::Div, I(Int(0))), F(Float(f64::INFINITY)));
+
+        let compare = |lhs: Number, rhs: Number| lhs.accept_binary(&rhs, &mut Comparator);
+        assert_eq!(compare(I(Int(1)), I(Int(2))), Some(Ordering::Less));
+        assert_eq!(compare(I(Int(2)), F(Float(1.5))), Some(Ordering::Greater));
+        assert_eq!(compare(F(Float(2.0)), I(Int(2))), Some(Ordering::Equal));
+        assert_eq!(compare(F(Float(f64::NAN)), I(Int(2))), None);
+    }
+
+    #[test]
+    fn ops_exam3() {
+        let n = |n: i32| Number::Int(Int(n));
+        let f = |f: f64| Number::Float(Float(f));
+
+        assert_eq!(n(1) + n(2), n(3));
+        assert_eq!(n(1) - f(0.5), f(0.5));
+        assert_eq!(f(1.5) * n(2), f(3.0));
+        assert_eq!(n(7) / n(2), Number::Rational(Rational(num::Rational::new(num::BigInt::from(7), num::BigInt::from(2)).unwrap())));
+        assert_eq!(-n(i32::MIN), Number::BigInt(BigInt(num::BigInt::from(2147483648i64))));
+        assert_eq!(-f(1.5), f(-1.5));
+        assert!((f(f64::NAN) + n(1)).as_f64().is_nan());
+
+        assert!(n(1) < n(2));
+        assert!(n(2) > f(1.5));
+        assert!(n(2) < f(2.0));
+        assert_eq!(f(f64::NAN).partial_cmp(&n(1)), None);
+
+        let mut numbers = vec![f(2.0), n(3), f(-1.5), n(2)];
+        numbers.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(numbers, vec![f(-1.5), n(2), f(2.0), n(3)]);
+
+        assert_eq!(numbers.iter().sum::<Number>(), f(5.5));
+        assert_eq!(vec![n(2), n(3)].into_iter().product::<Number>(), n(6));
+        assert_eq!(Vec::<Number>::new().into_iter().sum::<Number>(), n(0));
+    }
+
+    #[test]
+    fn overflow_exam3() {
+        let n = |n: i32| Number::Int(Int(n));
+        let f = |f: f64| Number::Float(Float(f));
+
+        let mut numbers: Vec<Number> = vec![n(i32::MAX - 1), n(1), f(1.5)];
+        let mut increaser = Increaser::new(2);
+        for n in numbers.iter_mut() {
+            n.accept(&mut increaser);
+        }
+        assert_eq!(numbers, vec![n(i32::MAX - 1), n(3), f(3.5)]);
+        assert_eq!(increaser.errors(), &[OverflowError { number: n(i32::MAX - 1), step: 2.0 }]);
+
+        let mut increaser = Increaser::with_overflow(2.0, Overflow::Saturating);
+        for n in numbers.iter_mut() {
+            n.accept(&mut increaser);
+        }
+        assert_eq!(numbers, vec![n(i32::MAX), n(5), f(5.5)]);
+
+        let mut decreaser = Decreaser::with_overflow(-1.0, Overflow::Wrapping);
+        for n in numbers.iter_mut() {
+            n.accept(&mut decreaser);
+        }
+        assert_eq!(numbers, vec![n(i32::MIN), n(6), f(6.5)]);
+
+        let mut decreaser = Decreaser::with_overflow(0.5, Overflow::Promote);
+        for n in numbers.iter_mut() {
+            n.accept(&mut decreaser);
+        }
+        assert_eq!(numbers, vec![n(i32::MIN), n(6), f(6.0)]);
+        assert_eq!(decreaser.errors().len(), 2);
+
+        let numbers: Vec<Number> = numbers.into_iter().map(|n| n.fold(&mut decreaser)).collect();
+        assert_eq!(numbers, vec![f(i32::MIN as f64 - 0.5), f(5.5), f(5.5)]);
+        assert_eq!(decreaser.errors().len(), 2);
+    }
+
+    #[test]
+    fn tower_exam3() {
+        let n = |n: i32| Number::Int(Int(n));
+        let f = |f: f64| Number::Float(Float(f));
+        let b = |b: &str| Number::BigInt(BigInt(b.parse().unwrap()));
+        let r = |n: i32, d: i32| Number::Rational(Rational(num::Rational::new(num::BigInt::from(n), num::BigInt::from(d)).unwrap()));
+        let d = |d: &str| Number::Decimal(Decimal(d.parse().unwrap()));
+        let c = |re: f64, im: f64| Number::Complex(Complex(num::Complex::new(re, im)));
+
+        assert_eq!(b("99999999999999999999") + n(1), b("100000000000000000000"));
+        assert_eq!(b("10") / n(4), r(5, 2));
+        assert_eq!(d("0.1") + d("0.2"), d("0.3"));
+        assert_eq!(d("1") / n(3), r(1, 3));
+        assert_eq!(r(1, 3) + n(1), r(4, 3));
+        assert_eq!(r(1, 2) * f(3.0), f(1.5));
+        assert_eq!(c(1.0, 2.0) * c(1.0, -2.0), c(5.0, 0.0));
+        assert_eq!(n(1) / b("0"), f(f64::INFINITY));
+        assert_eq!(-r(1, 2), r(-1, 2));
+
+        assert!(d("0.5") < r(2, 3));
+        assert!(b("99999999999999999999") > f(1e19));
+        assert!(d("0.5") < r(1, 2));
+        assert_eq!(c(1.0, 1.0).partial_cmp(&n(1)), None);
+        assert!(c(1.0, 1.0).as_f64().is_nan());
+
+        let mut numbers: Vec<Number> = vec![b("99999999999999999999"), r(1, 3), d("0.1"), c(1.0, 1.0)];
+        let mut increaser = Increaser::with_overflow(0.5, Overflow::Checked);
+        for n in numbers.iter_mut() {
+            n.accept(&mut increaser);
+        }
+        assert_eq!(numbers, vec![b("99999999999999999999"), r(5, 6), d("0.6"), c(1.5, 1.0)]);
+        assert_eq!(increaser.errors(), &[OverflowError { number: b("99999999999999999999"), step: 0.5 }]);
+
+        let mut decreaser = Decreaser::with_overflow(0.5, Overflow::Promote);
+        let numbers: Vec<Number> = numbers.into_iter().map(|n| n.fold(&mut decreaser)).collect();
+        assert_eq!(numbers, vec![d("99999999999999999998.5"), r(1, 3), d("0.1"), c(1.0, 1.0)]);
+
+        let mut accumulator = Accumulator::new(n(0), |acc, n| *acc = acc.clone() + n.clone());
+        for n in numbers.iter() {
+            n.accept_ref(&mut accumulator);
+        }
+        assert_eq!(accumulator.result(), &c(99999999999999999998.5 + 1.0 / 3.0 + 0.1 + 1.0, 1.0));
+    }
+
+    #[test]
+    fn parse_exam3() {
+        let n = |n: i32| Number::Int(Int(n));
+        let f = |f: f64| Number::Float(Float(f));
+
+        assert_eq!("-0x1F".parse(), Ok(n(-31)));
+        assert_eq!("1_000".parse(), Ok(n(1000)));
+        assert_eq!("1e3".parse(), Ok(f(1000.0)));
+        assert_eq!("2.0".parse(), Ok(f(2.0)));
+        assert_eq!("4294967296".parse(), Ok(Number::BigInt(BigInt(num::BigInt::from(4294967296i64)))));
+        assert_eq!("1.2.3".parse::<Number>(), Err(ParseLiteralError));
+
+        let numbers = parse_numbers("1, -2.5\t0xff,1e21 4294967296").unwrap();
+        assert_eq!(numbers[..4], vec![n(1), f(-2.5), n(255), f(1e21)]);
+        let printed: Vec<String> = numbers.iter().map(|n| n.to_string()).collect();
+        assert_eq!(printed, vec!["1", "-2.5", "255", "1e21", "4294967296"]);
+        assert_eq!(parse_numbers(&printed.join(" ")), Ok(numbers));
+
+        let error = parse_numbers("1 2,, 3").unwrap_err();
+        assert_eq!((error.index, error.offset, error.item.as_str()), (2, 4, ""));
+
+        let r = Number::Rational(Rational(num::Rational::new(num::BigInt::from(1), num::BigInt::from(3)).unwrap()));
+        let d = Number::Decimal(Decimal("2.675".parse().unwrap()));
+        let c = Number::Complex(Complex(num::Complex::new(1.0, -0.5)));
+        assert_eq!(
+            (format!("{:.2}", f(1.23456)), format!("{:.2}", n(3)), format!("{:>6.1}", f(2.0))),
+            ("1.23".to_string(), "3".to_string(), "   2.0".to_string()),
+        );
+        assert_eq!((r.to_string(), format!("{r:.3}")), ("1/3".to_string(), "0.333".to_string()));
+        assert_eq!((d.to_string(), format!("{d:.2}")), ("2.675".to_string(), "2.68".to_string()));
+        assert_eq!((c.to_string(), format!("{c:.1}")), ("1-0.5i".to_string(), "1.0-0.5i".to_string()));
+    }
+
+    #[test]
+    fn statistics_exam3() {
+        let n = |n: i32| Number::Int(Int(n));
+        let f = |f: f64| Number::Float(Float(f));
+
+        let numbers: Vec<Number> = vec![n(i32::MAX), n(i32::MAX), n(-4), n(8)];
+        let mut statistics = Statistics::new().with_quantiles();
+        for n in numbers.iter() {
+            n.accept_ref(&mut statistics);
+        }
+        assert_eq!(statistics.count(), 4);
+        assert_eq!(statistics.sum(), Number::BigInt(BigInt(num::BigInt::from(2 * i32::MAX as i64 + 4))));
+        assert_eq!((statistics.min(), statistics.max()), (Some(&n(-4)), Some(&n(i32::MAX))));
+        assert_eq!(statistics.median(), Some((8.0 + i32::MAX as f64) / 2.0));
+
+        let mut numbers = parse_numbers("2, 4, 4, 4, 5, 5, 7, 9").unwrap();
+        numbers.push(Number::Decimal(Decimal("0.5".parse().unwrap())));
+        numbers.push(f(f64::NAN));
+        let mut statistics = Statistics::new().with_quantiles().with_histogram(0.0, 10.0, 2);
+        for n in numbers.iter_mut() {
+            n.accept(&mut statistics);
+        }
+        assert_eq!(statistics.count(), 10);
+        assert!(statistics.sum().as_f64().is_nan());
+        assert_eq!((statistics.min(), statistics.max()), (Some(&Number::Decimal(Decimal("0.5".parse().unwrap()))), Some(&n(9))));
+        assert_eq!(statistics.mean(), Some(40.5 / 9.0));
+        assert_eq!((statistics.median(), statistics.quantile(0.25)), (Some(4.0), Some(4.0)));
+        assert_eq!(statistics.histogram().unwrap().counts(), &[5, 4]);
+
+        let mut statistics = Statistics::new();
+        for n in parse_numbers("2, 4, 4, 4, 5, 5, 7, 9").unwrap().iter() {
+            n.accept_ref(&mut statistics);
+        }
+        assert_eq!((statistics.sum(), statistics.mean(), statistics.stddev()), (n(40), Some(5.0), Some(2.0)));
+        assert_eq!((statistics.median(), Statistics::new().sum(), Statistics::new().mean()), (None, n(0), None));
+
+        let mut statistics = Statistics::new();
+        for n in [f(0.25), f(0.5), Number::Decimal(Decimal("0.125".parse().unwrap())), Number::Complex(Complex(num::Complex::new(1.0, 2.0)))].iter() {
+            n.accept_ref(&mut statistics);
+        }
+        assert_eq!(statistics.sum(), Number::Complex(Complex(num::Complex::new(1.875, 2.0))));
+        assert_eq!(statistics.count(), 4);
+    }
+
+    #[test]
+    fn fan_out_exam3() {
+        let n = |n: i32| Number::Int(Int(n));
+        let f = |f: f64| Number::Float(Float(f));
+
+        let mut numbers: Vec<Number> = vec![n(1), n(2), f(3.5)];
+        let mut increaser = Increaser::new(2);
+        let mut accumulator = Accumulator::new(0.0, |acc, n| *acc += n.as_f64());
+        let mut fan_out: FanOut<dyn Visitor> = FanOut::new(vec![&mut increaser, &mut accumulator]);
+        for n in numbers.iter_mut() {
+            n.accept(&mut fan_out);
+        }
+        assert_eq!(numbers, vec![n(3), n(4), f(5.5)]);
+        assert_eq!(accumulator.result(), &12.5);
+
+        let mut statistics = Statistics::new();
+        let mut accumulator = Accumulator::new(Vec::new(), |acc, n| acc.push(n.clone()));
+        let mut fan_out: FanOut<dyn Visit> = FanOut::new(vec![&mut statistics, &mut accumulator]);
+        for n in numbers.iter() {
+            n.accept_ref(&mut fan_out);
+        }
+        assert_eq!((statistics.sum(), accumulator.result()), (f(12.5), &numbers));
+
+        let mut add = Calculator::new(Operator::Add);
+        let mut mul = Calculator::new(Operator::Mul);
+        let mut fan_out: FanOut<dyn BinaryVisitor<Number>> = FanOut::new(vec![&mut add, &mut mul]);
+        assert_eq!(n(3).accept_binary(&f(0.5), &mut fan_out), vec![f(3.5), f(1.5)]);
+    }
+
+    #[test]
+    fn accept_all_exam3() {
+        let n = |n: i32| Number::Int(Int(n));
+        let f = |f: f64| Number::Float(Float(f));
+
+        let mut numbers = vec![n(1), n(2), f(3.5)];
+        numbers.iter_mut().accept_all(&mut Increaser::new(2));
+        assert_eq!(numbers, vec![n(3), n(4), f(5.5)]);
+        let statistics = numbers.iter().visit_with(Statistics::new());
+        assert_eq!((statistics.count(), statistics.mean()), (3, Some(12.5 / 3.0)));
+
+        let numbers = numbers.into_iter().accept_each(&mut Rounder);
+        assert_eq!(numbers, vec![n(3), n(4), n(6)]);
+        let rhs = [n(4), f(4.0), n(5)];
+        let orderings = numbers.iter().zip(rhs.iter()).accept_each(&mut Comparator);
+        assert_eq!(orderings, vec![Some(Ordering::Less), Some(Ordering::Equal), Some(Ordering::Greater)]);
+        let sums = numbers.iter().zip(rhs.iter()).accept_each(&mut Calculator::new(Operator::Add));
+        assert_eq!(sums, vec![n(7), f(8.0), n(11)]);
+    }
+
+    #[test]
+    fn pipeline_exam3() {
+        let n = |n: i32| Number::Int(Int(n));
+        let f = |f: f64| Number::Float(Float(f));
+
+        let visitors: Vec<Box<dyn Visitor>> = "inc 2, dec 1, inc 3".split(", ").map(|stage| {
+            let (name, step) = stage.split_once(' ').unwrap();
+            let step: u16 = step.parse().unwrap();
+            match name {
+                "inc" => Box::new(Increaser::new(step)) as Box<dyn Visitor>,
+                _ => Box::new(Decreaser::new(step)),
+            }
+        }).collect();
+        let mut numbers = vec![n(1), f(2.5)];
+        Pipeline::from(visitors).apply(&mut numbers);
+        assert_eq!(numbers, vec![n(5), f(6.5)]);
+
+        let mut visitor: Box<dyn Visitor> = Box::new(Increaser::new(1));
+        numbers[0].accept(visitor.as_mut());
+        assert_eq!(numbers[0], n(6));
+
+        let mut statistics = Statistics::new();
+        let mut pipeline = Pipeline::new().with_folder(Rounder).with_visitor(&mut statistics).with_visitor(Increaser::new(1));
+        assert_eq!((pipeline.len(), pipeline.is_empty()), (3, false));
+        pipeline.apply(&mut numbers);
+        drop(pipeline);
+        assert_eq!(numbers, vec![n(7), n(8)]);
+        assert_eq!((statistics.count(), statistics.mean()), (2, Some(6.5)));
+    }
+
+    #[test]
+    fn parallel_exam3() {
+        let n = |n: i32| Number::Int(Int(n));
+
+        let mut numbers: Vec<Number> = (1..=10_000).map(n).collect();
+        numbers[1234] = n(i32::MAX);
+        numbers[8765] = n(i32::MAX - 1);
+        let sum = |threads| {
+            let mut accumulator = ParAccumulator::new(0i64, |acc, n| *acc += n.as_f64() as i64, |acc, other| *acc += other);
+            par_accept_ref(&numbers, &mut accumulator, threads);
+            *accumulator.result()
+        };
+        let expected = (1..=10_000).sum::<i64>() - 1235 - 8766 + 2 * i32::MAX as i64 - 1;
+        assert_eq!((sum(1), sum(4), sum(0), sum(20_000)), (expected, expected, expected, expected));
+
+        // An `Accumulator` which is never split may borrow what is not `Sync`.
+        let visited = std::cell::Cell::new(0);
+        let mut accumulator = Accumulator::new((), |_, _| visited.set(visited.get() + 1));
+        numbers.iter().for_each(|number| number.accept_ref(&mut accumulator));
+        assert_eq!(visited.get(), 10_000);
+
+        let statistics = |threads| {
+            let mut statistics = Statistics::new().with_quantiles().with_histogram(0.0, 10_000.0, 4);
+            par_accept_ref(&numbers, &mut statistics, threads);
+            statistics
+        };
+        let (sequential, parallel) = (statistics(1), statistics(7));
+        assert_eq!((parallel.count(), parallel.sum()), (sequential.count(), sequential.sum()));
+        assert_eq!((parallel.min(), parallel.max()), (Some(&n(1)), Some(&n(i32::MAX))));
+        assert_eq!((parallel.median(), parallel.histogram()), (sequential.median(), sequential.histogram()));
+        assert!((parallel.mean().unwrap() - sequential.mean().unwrap()).abs() < 1e-6);
+        assert_eq!(parallel.variance(), statistics(7).variance());
+
+        let mut increaser = Increaser::new(1);
+        par_accept(&mut numbers, &mut increaser, 3);
+        assert_eq!((&numbers[..2], &numbers[8765]), (&[n(2), n(3)][..], &n(i32::MAX)));
+        let errors: Vec<&Number> = increaser.errors().iter().map(|error| &error.number).collect();
+        assert_eq!(errors, vec![&n(i32::MAX)]);
+        let mut decreaser = Decreaser::new(1);
+        par_accept(&mut numbers, &mut decreaser, 3);
+        assert_eq!((&numbers[..2], &numbers[1234], decreaser.errors().len()), (&[n(1), n(2)][..], &n(i32::MAX - 1), 0));
+    }
+}
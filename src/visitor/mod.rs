@@ -1,78 +1,822 @@
 pub mod exam1;
 pub mod exam2;
 pub mod exam3;
+pub mod acyclic;
+pub mod bridge;
 
-pub trait Element {
-    // NOTE: If a method has generic type parameters or impl Trait types,
-    //       the trait `Element` cannot be made into an object.
-    // fn accept(&mut self, visitor: &mut impl Visitor);
-    fn accept(&mut self, visitor: &mut dyn Visitor);
+use std::any::Any;
+use std::fmt;
+use std::io::{self, Stdout, Write};
+use std::marker::PhantomData;
+use std::thread;
+use gof_derive::{visitor, Visitable};
+
+// Generates the trait `Element` accepting the visitors, the traits `Visitor` and `Visit`, and `Control`,
+// which controls how a traversal proceeds after entering a composite element.
+// `#[derive(Visitable)]` implements `Element` for the element types, and the walks of `CompositeElement`,
+// so a visitor overriding only some methods still reaches all descendants.
+// NOTE: `Visit` is the read-only counterpart of `Visitor`, like `Visit` and `VisitMut` in syn.
+//       Elements are borrowed immutably, so a shared tree can be visited.
+// NOTE: The hierarchy is open, so an element kind unknown to the visitors, such as one added by
+//       a downstream crate, calls `visit_other` from its `accept_at`, and adding it does not break existing visitors.
+visitor! {
+    accept = Element, visit_mut = Visitor;
+    ElementA,
+    ElementB,
+    CompositeElement { .. },
+    ..
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Visitable)]
+pub struct ElementA {
+    payload: String,
 }
 
-pub struct ElementA;
+impl ElementA {
+    pub fn new(payload: &str) -> Self {
+        ElementA { payload: payload.to_string() }
+    }
 
-impl Element for ElementA {
-    fn accept(&mut self, visitor: &mut dyn Visitor) {
-        visitor.visit_element_a(self);
+    pub fn payload(&self) -> &str {
+        &self.payload
     }
 }
 
-pub struct ElementB;
+#[derive(Debug, Clone, Default, PartialEq, Eq, Visitable)]
+pub struct ElementB {
+    payload: String,
+}
 
-impl Element for ElementB {
-    fn accept(&mut self, visitor: &mut dyn Visitor) {
-        visitor.visit_element_b(self);
+impl ElementB {
+    pub fn new(payload: &str) -> Self {
+        ElementB { payload: payload.to_string() }
+    }
+
+    pub fn payload(&self) -> &str {
+        &self.payload
     }
 }
 
+#[derive(Default, Visitable)]
 pub struct CompositeElement {
+    payload: String,
+    #[visitable(children)]
     children: Vec<Box<dyn Element>>,
 }
 
-impl Element for CompositeElement {
-    fn accept(&mut self, visitor: &mut dyn Visitor) {
-        for child in self.children.iter_mut() {
-            child.accept(visitor);
+impl CompositeElement {
+    pub fn new(payload: &str) -> Self {
+        CompositeElement {
+            payload: payload.to_string(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_child(mut self, child: impl Element + 'static) -> Self {
+        self.add(Box::new(child));
+        self
+    }
+
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    pub fn add(&mut self, child: Box<dyn Element>) {
+        self.children.push(child);
+    }
+
+    // Removes the child at an index, or returns `None` if the index is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<Box<dyn Element>> {
+        (index < self.children.len()).then(|| self.children.remove(index))
+    }
+
+    pub fn children(&self) -> &[Box<dyn Element>] {
+        &self.children
+    }
+
+    pub fn children_mut(&mut self) -> &mut [Box<dyn Element>] {
+        &mut self.children
+    }
+}
+
+pub trait ValueVisitor<R> {
+    fn visit_element_a(&mut self, element: &mut ElementA) -> R;
+    fn visit_element_b(&mut self, element: &mut ElementB) -> R;
+    fn visit_composite_element(&mut self, element: &mut CompositeElement, children: Vec<R>) -> R;
+
+    // NOTE: Unlike `Visitor`, a value visitor has no result to fall back on for an element kind it does not know,
+    //       so the method has no default body and each visitor gives its own fallback.
+    fn visit_other(&mut self, element: &mut dyn Any) -> R;
+}
+
+impl dyn Element + '_ {
+    // NOTE: `accept_value` is not a method of the trait `Element`, because a generic method
+    //       would prevent the trait from being made into an object.
+    pub fn accept_value<R>(&mut self, visitor: &mut dyn ValueVisitor<R>) -> R {
+        let mut adapter = ValueAdapter { visitor, results: Vec::new() };
+        self.accept(&mut adapter);
+        adapter.results.pop().expect("an accepted element must produce a result")
+    }
+}
+
+// Adapts a `ValueVisitor` to a `Visitor` by keeping the results on a stack.
+// Because children are visited before their composite, the last results on the stack
+// are the results of the composite's children.
+struct ValueAdapter<'a, R> {
+    visitor: &'a mut dyn ValueVisitor<R>,
+    results: Vec<R>,
+}
+
+impl<R> Visitor for ValueAdapter<'_, R> {
+    fn visit_element_a(&mut self, element: &mut ElementA) {
+        let result = self.visitor.visit_element_a(element);
+        self.results.push(result);
+    }
+
+    fn visit_element_b(&mut self, element: &mut ElementB) {
+        let result = self.visitor.visit_element_b(element);
+        self.results.push(result);
+    }
+
+    fn visit_composite_element(&mut self, element: &mut CompositeElement) {
+        let children = self.results.split_off(self.results.len() - element.children.len());
+        let result = self.visitor.visit_composite_element(element, children);
+        self.results.push(result);
+    }
+
+    fn visit_other(&mut self, element: &mut dyn Any) {
+        let result = self.visitor.visit_other(element);
+        self.results.push(result);
+    }
+}
+
+// Fans one traversal out to several visitors in order, so each visitor receives its callbacks
+// in the same order as if it traversed alone. A visitor returning `SkipChildren` or `Stop` from a hook
+// no longer receives the callbacks of the skipped descendants or of the rest of the traversal,
+// while the other visitors go on. The traversal itself skips or stops only when all visitors do.
+pub struct FanOut<'a, V: ?Sized> {
+    visitors: Vec<&'a mut V>,
+    fans: Vec<Fan>,
+    open: usize,
+}
+
+// The state of a visitor in a fan-out. `Skipping` holds the number of composites
+// which were open when the visitor skipped the children of the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fan {
+    Active,
+    Skipping(usize),
+    Stopped,
+}
+
+impl<'a, V: ?Sized> FanOut<'a, V> {
+    pub fn new(visitors: Vec<&'a mut V>) -> Self {
+        let fans = vec![Fan::Active; visitors.len()];
+        FanOut { visitors, fans, open: 0 }
+    }
+
+    // Calls the visitors receiving the callbacks of a leaf.
+    fn leaf(&mut self, mut f: impl FnMut(&mut V)) {
+        for (visitor, fan) in self.visitors.iter_mut().zip(self.fans.iter()) {
+            if *fan == Fan::Active {
+                f(&mut **visitor);
+            }
+        }
+    }
+
+    // Calls the visitors receiving the callbacks of a leaf which may stop the traversal,
+    // and stops it once all the visitors have stopped.
+    fn leaf_control(&mut self, mut f: impl FnMut(&mut V) -> Control) -> Control {
+        for (visitor, fan) in self.visitors.iter_mut().zip(self.fans.iter_mut()) {
+            if *fan == Fan::Active && f(&mut **visitor) == Control::Stop {
+                *fan = Fan::Stopped;
+            }
+        }
+        if self.fans.iter().all(|fan| *fan == Fan::Stopped) { Control::Stop } else { Control::Continue }
+    }
+
+    // Calls the visitors receiving the callbacks of a leaf, and collects their results.
+    fn leaf_value<R>(&mut self, mut f: impl FnMut(&mut V) -> R) -> Vec<R> {
+        self.visitors.iter_mut().map(|visitor| f(&mut **visitor)).collect()
+    }
+
+    // Calls the visitors entering a composite, and combines their controls.
+    fn enter(&mut self, mut f: impl FnMut(&mut V) -> Control) -> Control {
+        let open = self.open;
+        for (visitor, fan) in self.visitors.iter_mut().zip(self.fans.iter_mut()) {
+            if *fan == Fan::Active {
+                match f(&mut **visitor) {
+                    Control::Continue => {},
+                    Control::SkipChildren => *fan = Fan::Skipping(open),
+                    Control::Stop => *fan = Fan::Stopped,
+                }
+            }
+        }
+        if self.fans.iter().all(|fan| *fan == Fan::Stopped) {
+            return Control::Stop;
+        }
+        self.open += 1;
+        if self.fans.contains(&Fan::Active) { Control::Continue } else { Control::SkipChildren }
+    }
+
+    // Calls the visitors receiving the callbacks of the innermost open composite,
+    // which are the active ones and the ones skipping its children.
+    fn composite(&mut self, mut f: impl FnMut(&mut V)) {
+        let skipping = Fan::Skipping(self.open - 1);
+        for (visitor, fan) in self.visitors.iter_mut().zip(self.fans.iter()) {
+            if *fan == Fan::Active || *fan == skipping {
+                f(&mut **visitor);
+            }
+        }
+    }
+
+    // Calls the visitors receiving the callbacks of the innermost open composite which may stop the traversal,
+    // and stops it once all the visitors have stopped.
+    fn composite_control(&mut self, mut f: impl FnMut(&mut V) -> Control) -> Control {
+        let skipping = Fan::Skipping(self.open - 1);
+        for (visitor, fan) in self.visitors.iter_mut().zip(self.fans.iter_mut()) {
+            if (*fan == Fan::Active || *fan == skipping) && f(&mut **visitor) == Control::Stop {
+                *fan = Fan::Stopped;
+            }
+        }
+        if self.fans.iter().all(|fan| *fan == Fan::Stopped) { Control::Stop } else { Control::Continue }
+    }
+
+    // Calls the visitors leaving the innermost open composite, which become active again.
+    fn leave(&mut self, mut f: impl FnMut(&mut V)) {
+        self.open -= 1;
+        let skipping = Fan::Skipping(self.open);
+        for (visitor, fan) in self.visitors.iter_mut().zip(self.fans.iter_mut()) {
+            if *fan == Fan::Active || *fan == skipping {
+                f(&mut **visitor);
+                *fan = Fan::Active;
+            }
+        }
+    }
+}
+
+// Transposes the results of the children, one `Vec` per child with one result per visitor,
+// into one `Vec` per visitor with one result per child.
+fn transpose<R>(children: Vec<Vec<R>>, visitors: usize) -> Vec<Vec<R>> {
+    let mut columns: Vec<Vec<R>> = (0..visitors).map(|_| Vec::with_capacity(children.len())).collect();
+    for child in children {
+        for (column, result) in columns.iter_mut().zip(child) {
+            column.push(result);
         }
-        visitor.visit_composite_element(self);
+    }
+    columns
+}
+
+impl Visitor for FanOut<'_, dyn Visitor + '_> {
+    fn visit_element_a(&mut self, element: &mut ElementA) {
+        self.leaf(|visitor| visitor.visit_element_a(element));
+    }
+
+    fn visit_element_b(&mut self, element: &mut ElementB) {
+        self.leaf(|visitor| visitor.visit_element_b(element));
+    }
+
+    fn visit_composite_element(&mut self, element: &mut CompositeElement) {
+        self.composite(|visitor| visitor.visit_composite_element(element));
+    }
+
+    fn visit_other(&mut self, element: &mut dyn Any) {
+        self.leaf(|visitor| visitor.visit_other(element));
+    }
+
+    fn enter_composite_element(&mut self, element: &mut CompositeElement, depth: usize) -> Control {
+        self.enter(|visitor| visitor.enter_composite_element(element, depth))
+    }
+
+    fn leave_composite_element(&mut self, element: &mut CompositeElement, depth: usize) {
+        self.leave(|visitor| visitor.leave_composite_element(element, depth));
+    }
+}
+
+impl Visit for FanOut<'_, dyn Visit + '_> {
+    fn visit_element_a(&mut self, element: &ElementA) {
+        self.leaf(|visitor| visitor.visit_element_a(element));
+    }
+
+    fn visit_element_b(&mut self, element: &ElementB) {
+        self.leaf(|visitor| visitor.visit_element_b(element));
+    }
+
+    fn visit_composite_element(&mut self, element: &CompositeElement) {
+        self.composite(|visitor| visitor.visit_composite_element(element));
+    }
+
+    fn visit_other(&mut self, element: &dyn Any) {
+        self.leaf(|visitor| visitor.visit_other(element));
+    }
+
+    fn enter_composite_element(&mut self, element: &CompositeElement, depth: usize) -> Control {
+        self.enter(|visitor| visitor.enter_composite_element(element, depth))
+    }
+
+    fn leave_composite_element(&mut self, element: &CompositeElement, depth: usize) {
+        self.leave(|visitor| visitor.leave_composite_element(element, depth));
+    }
+}
+
+// NOTE: Value visitors have no hooks, so every visitor receives every callback,
+//       and the fan-out produces the results of all visitors in order.
+impl<R> ValueVisitor<Vec<R>> for FanOut<'_, dyn ValueVisitor<R> + '_> {
+    fn visit_element_a(&mut self, element: &mut ElementA) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_element_a(element))
+    }
+
+    fn visit_element_b(&mut self, element: &mut ElementB) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_element_b(element))
+    }
+
+    fn visit_composite_element(&mut self, element: &mut CompositeElement, children: Vec<Vec<R>>) -> Vec<R> {
+        let columns = transpose(children, self.visitors.len());
+        self.visitors.iter_mut().zip(columns).map(|(visitor, children)| visitor.visit_composite_element(element, children)).collect()
+    }
+
+    fn visit_other(&mut self, element: &mut dyn Any) -> Vec<R> {
+        self.leaf_value(|visitor| visitor.visit_other(element))
+    }
+}
+
+// A visitor which can visit parts of a collection separately, such as on several threads,
+// and merge the results back.
+pub trait Merge {
+    // Creates a visitor with the same configuration and no results, to visit a part.
+    fn split(&self) -> Self;
+
+    // Merges the results of a visitor which visited the part following the parts visited by this visitor.
+    fn merge(&mut self, other: Self);
+}
+
+// Visits `items` in contiguous chunks, one per thread, each by a split of `visitor`,
+// and merges the splits back into `visitor` in the order of the chunks.
+// NOTE: The merge order does not depend on the scheduling of the threads,
+//       so the results are deterministic if the merges are associative.
+fn par_visit<T, V>(items: &[T], visitor: &mut V, threads: usize, visit: impl Fn(&T, &mut V) + Sync)
+where
+    T: Sync,
+    V: Merge + Send,
+{
+    let size = items.len().div_ceil(threads.max(1)).max(1);
+    let splits: Vec<V> = thread::scope(|scope| {
+        let handles: Vec<_> = items.chunks(size).map(|chunk| {
+            let (mut split, visit) = (visitor.split(), &visit);
+            scope.spawn(move || {
+                chunk.iter().for_each(|item| visit(item, &mut split));
+                split
+            })
+        }).collect();
+        handles.into_iter().map(|handle| handle.join().expect("a visiting thread panicked")).collect()
+    });
+    splits.into_iter().for_each(|split| visitor.merge(split));
+}
+
+// Same as `par_visit`, but visits the items mutably.
+fn par_visit_mut<T, V>(items: &mut [T], visitor: &mut V, threads: usize, visit: impl Fn(&mut T, &mut V) + Sync)
+where
+    T: Send,
+    V: Merge + Send,
+{
+    let size = items.len().div_ceil(threads.max(1)).max(1);
+    let splits: Vec<V> = thread::scope(|scope| {
+        let handles: Vec<_> = items.chunks_mut(size).map(|chunk| {
+            let (mut split, visit) = (visitor.split(), &visit);
+            scope.spawn(move || {
+                chunk.iter_mut().for_each(|item| visit(item, &mut split));
+                split
+            })
+        }).collect();
+        handles.into_iter().map(|handle| handle.join().expect("a visiting thread panicked")).collect()
+    });
+    splits.into_iter().for_each(|split| visitor.merge(split));
+}
+
+// Accepts a visitor by an item of an iterator, such as `&mut Box<dyn Element>` for a `Visitor`
+// and `&Box<dyn Element>` for a `Visit`. The marker `M` tells apart the visitor traits,
+// so that a visitor may implement several of them for the same item.
+pub trait Accept<V, M> {
+    type Output;
+
+    fn accept_with(self, visitor: &mut V) -> Self::Output;
+}
+
+// The marker of the visitor traits without results, such as `Visitor`, `Visit` and `Folder`.
+pub struct ByVisitor;
+
+// The marker of the visitor traits with results, such as `ValueVisitor<R>` and `BinaryVisitor<R>`.
+pub struct ByValue<R>(PhantomData<R>);
+
+// The marker of the fallible visitor traits, such as `TryEquipmentVisitor<E>`.
+pub struct ByTry<E>(PhantomData<E>);
+
+// Accepts a visitor by each item of an iterator, such as `elements.iter_mut().accept_all(&mut visitor)`.
+// NOTE: If a visitor implements several visitor traits for the same item, the trait is chosen
+//       by annotating the marker, such as `accept_all::<_, ByVisitor>`.
+pub trait AcceptAll: Iterator + Sized {
+    fn accept_all<V, M>(self, visitor: &mut V)
+    where
+        Self::Item: Accept<V, M>,
+    {
+        self.for_each(|item| _ = item.accept_with(visitor));
+    }
+
+    // Returns the outputs of the items, such as the results of a `ValueVisitor` or the numbers folded by a `Folder`.
+    fn accept_each<V, M>(self, visitor: &mut V) -> Vec<<Self::Item as Accept<V, M>>::Output>
+    where
+        Self::Item: Accept<V, M>,
+    {
+        self.map(|item| item.accept_with(visitor)).collect()
+    }
+
+    // Returns the visitor, to extract its results.
+    fn visit_with<V, M>(self, mut visitor: V) -> V
+    where
+        Self::Item: Accept<V, M>,
+    {
+        self.accept_all(&mut visitor);
+        visitor
+    }
+}
+
+impl<I: Iterator> AcceptAll for I {}
+
+impl<V: Visitor> Accept<V, ByVisitor> for &mut Box<dyn Element + '_> {
+    type Output = Control;
+
+    fn accept_with(self, visitor: &mut V) -> Control {
+        self.accept(visitor)
+    }
+}
+
+impl<V: Visit> Accept<V, ByVisitor> for &Box<dyn Element + '_> {
+    type Output = Control;
+
+    fn accept_with(self, visitor: &mut V) -> Control {
+        self.accept_ref(visitor)
     }
 }
 
-pub trait Visitor {
-    fn visit_element_a(&mut self, element: &mut ElementA);
-    fn visit_element_b(&mut self, element: &mut ElementB);
-    fn visit_composite_element(&mut self, element: &mut CompositeElement);
+impl<R, V: ValueVisitor<R>> Accept<V, ByValue<R>> for &mut Box<dyn Element + '_> {
+    type Output = R;
+
+    fn accept_with(self, visitor: &mut V) -> R {
+        self.accept_value(visitor)
+    }
+}
+
+// NOTE: The demo visitors write to stdout by default, and to any writer given by `with_writer`.
+//       Like `println!`, they panic if writing fails.
+pub struct Visitor1<W = Stdout> {
+    out: W,
 }
 
-pub struct Visitor1;
+impl Visitor1 {
+    pub fn new() -> Self {
+        Self::with_writer(io::stdout())
+    }
+}
+
+impl Default for Visitor1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write> Visitor1<W> {
+    pub fn with_writer(out: W) -> Self {
+        Visitor1 { out }
+    }
+
+    pub fn into_writer(self) -> W {
+        self.out
+    }
+
+    fn print(&mut self, method: &str) {
+        writeln!(self.out, "# Visitor1::{method}").expect("failed to write a visit");
+    }
+}
 
-impl Visitor for Visitor1 {
+impl<W: Write> Visitor for Visitor1<W> {
     fn visit_element_a(&mut self, _element: &mut ElementA) {
-        println!("# Visitor1::visit_element_a");
+        self.print("visit_element_a");
     }
 
     fn visit_element_b(&mut self, _element: &mut ElementB) {
-        println!("# Visitor1::visit_element_b");
+        self.print("visit_element_b");
     }
 
     fn visit_composite_element(&mut self, _element: &mut CompositeElement) {
-        println!("# Visitor1::visit_composite_element");
+        self.print("visit_composite_element");
+    }
+}
+
+impl<W: Write> Visit for Visitor1<W> {
+    fn visit_element_a(&mut self, _element: &ElementA) {
+        self.print("visit_element_a");
+    }
+
+    fn visit_element_b(&mut self, _element: &ElementB) {
+        self.print("visit_element_b");
+    }
+
+    fn visit_composite_element(&mut self, _element: &CompositeElement) {
+        self.print("visit_composite_element");
+    }
+}
+
+pub struct Visitor2<W = Stdout> {
+    out: W,
+}
+
+impl Visitor2 {
+    pub fn new() -> Self {
+        Self::with_writer(io::stdout())
     }
 }
 
-pub struct Visitor2;
+impl Default for Visitor2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write> Visitor2<W> {
+    pub fn with_writer(out: W) -> Self {
+        Visitor2 { out }
+    }
+
+    pub fn into_writer(self) -> W {
+        self.out
+    }
+
+    fn print(&mut self, method: &str) {
+        writeln!(self.out, "# Visitor2::{method}").expect("failed to write a visit");
+    }
+}
 
-impl Visitor for Visitor2 {
+impl<W: Write> Visitor for Visitor2<W> {
     fn visit_element_a(&mut self, _element: &mut ElementA) {
-        println!("# Visitor2::visit_element_a");
+        self.print("visit_element_a");
     }
 
     fn visit_element_b(&mut self, _element: &mut ElementB) {
-        println!("# Visitor2::visit_element_b");
+        self.print("visit_element_b");
     }
 
     fn visit_composite_element(&mut self, _element: &mut CompositeElement) {
-        println!("# Visitor2::visit_composite_element");
+        self.print("visit_composite_element");
+    }
+}
+
+impl<W: Write> Visit for Visitor2<W> {
+    fn visit_element_a(&mut self, _element: &ElementA) {
+        self.print("visit_element_a");
+    }
+
+    fn visit_element_b(&mut self, _element: &ElementB) {
+        self.print("visit_element_b");
+    }
+
+    fn visit_composite_element(&mut self, _element: &CompositeElement) {
+        self.print("visit_composite_element");
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementKind {
+    A,
+    B,
+    Composite,
+    Other,
+}
+
+impl fmt::Display for ElementKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ElementKind::A => "a",
+            ElementKind::B => "b",
+            ElementKind::Composite => "composite",
+            ElementKind::Other => "other",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Enter,
+    Visit,
+    Leave,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::Enter => "enter",
+            Phase::Visit => "visit",
+            Phase::Leave => "leave",
+        })
+    }
+}
+
+// A callback received by a `TracingVisitor`. `order` counts the events from 0,
+// and `depth` is the depth of the element, which is 0 for the accepted one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub order: usize,
+    pub depth: usize,
+    pub phase: Phase,
+    pub kind: ElementKind,
+    pub payload: String,
+}
+
+// Formats an event as a line indented by its depth, such as `3     visit a x`.
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:indent$}{} {}", self.order, "", self.phase, self.kind, indent = self.depth * 2)?;
+        if !self.payload.is_empty() {
+            write!(f, " {}", self.payload)?;
+        }
+        Ok(())
+    }
+}
+
+pub trait TraceSink {
+    fn record(&mut self, event: TraceEvent);
+}
+
+// Keeps the events in memory.
+impl TraceSink for Vec<TraceEvent> {
+    fn record(&mut self, event: TraceEvent) {
+        self.push(event);
+    }
+}
+
+impl<S: TraceSink + ?Sized> TraceSink for &mut S {
+    fn record(&mut self, event: TraceEvent) {
+        (**self).record(event);
+    }
+}
+
+// Writes the events one per line. Like `println!`, it panics if writing fails.
+pub struct WriterSink<W> {
+    out: W,
+}
+
+impl<W: Write> WriterSink<W> {
+    pub fn new(out: W) -> Self {
+        WriterSink { out }
+    }
+
+    pub fn into_writer(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> TraceSink for WriterSink<W> {
+    fn record(&mut self, event: TraceEvent) {
+        writeln!(self.out, "{event}").expect("failed to write a trace event");
+    }
+}
+
+// Passes the events to a callback.
+pub struct CallbackSink<F> {
+    callback: F,
+}
+
+impl<F: FnMut(TraceEvent)> CallbackSink<F> {
+    pub fn new(callback: F) -> Self {
+        CallbackSink { callback }
+    }
+}
+
+impl<F: FnMut(TraceEvent)> TraceSink for CallbackSink<F> {
+    fn record(&mut self, event: TraceEvent) {
+        (self.callback)(event);
+    }
+}
+
+// Records every callback of a traversal into a sink, including the hooks.
+pub struct TracingVisitor<S> {
+    sink: S,
+    order: usize,
+    // The depths of the entered composites which are not left yet.
+    open: Vec<usize>,
+}
+
+impl<S: TraceSink> TracingVisitor<S> {
+    pub fn new(sink: S) -> Self {
+        TracingVisitor { sink, order: 0, open: Vec::new() }
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+
+    fn record(&mut self, depth: usize, phase: Phase, kind: ElementKind, payload: &str) {
+        let event = TraceEvent { order: self.order, depth, phase, kind, payload: payload.to_string() };
+        self.order += 1;
+        self.sink.record(event);
+    }
+
+    // The children of a composite are one deeper than the composite, which is visited and left at its own depth.
+    // NOTE: An element visited without entering a composite, as when a callback is called directly, is at depth 0.
+    fn visit(&mut self, kind: ElementKind, payload: &str) {
+        let depth = self.open.last().map_or(0, |depth| depth + 1);
+        self.record(depth, Phase::Visit, kind, payload);
+    }
+
+    fn enter(&mut self, payload: &str, depth: usize) -> Control {
+        self.record(depth, Phase::Enter, ElementKind::Composite, payload);
+        self.open.push(depth);
+        Control::Continue
+    }
+
+    fn visit_composite(&mut self, payload: &str) {
+        let depth = self.open.last().copied().unwrap_or(0);
+        self.record(depth, Phase::Visit, ElementKind::Composite, payload);
+    }
+
+    fn leave(&mut self, payload: &str, depth: usize) {
+        self.open.pop();
+        self.record(depth, Phase::Leave, ElementKind::Composite, payload);
+    }
+}
+
+impl<S: TraceSink> Visitor for TracingVisitor<S> {
+    fn visit_element_a(&mut self, element: &mut ElementA) {
+        self.visit(ElementKind::A, &element.payload);
+    }
+
+    fn visit_element_b(&mut self, element: &mut ElementB) {
+        self.visit(ElementKind::B, &element.payload);
+    }
+
+    fn visit_composite_element(&mut self, element: &mut CompositeElement) {
+        self.visit_composite(&element.payload);
+    }
+
+    fn visit_other(&mut self, _element: &mut dyn Any) {
+        self.visit(ElementKind::Other, "");
+    }
+
+    fn enter_composite_element(&mut self, element: &mut CompositeElement, depth: usize) -> Control {
+        self.enter(&element.payload, depth)
+    }
+
+    fn leave_composite_element(&mut self, element: &mut CompositeElement, depth: usize) {
+        self.leave(&element.payload, depth);
+    }
+}
+
+impl<S: TraceSink> Visit for TracingVisitor<S> {
+    fn visit_element_a(&mut self, element: &ElementA) {
+        self.visit(ElementKind::A, &element.payload);
+    }
+
+    fn visit_element_b(&mut self, element: &ElementB) {
+        self.visit(ElementKind::B, &element.payload);
+    }
+
+    fn visit_composite_element(&mut self, element: &CompositeElement) {
+        self.visit_composite(&element.payload);
+    }
+
+    fn visit_other(&mut self, _element: &dyn Any) {
+        self.visit(ElementKind::Other, "");
+    }
+
+    fn enter_composite_element(&mut self, element: &CompositeElement, depth: usize) -> Control {
+        self.enter(&element.payload, depth)
+    }
+
+    fn leave_composite_element(&mut self, element: &CompositeElement, depth: usize) {
+        self.leave(&element.payload, depth);
+    }
+}
+
+pub struct Counter;
+
+impl ValueVisitor<usize> for Counter {
+    fn visit_element_a(&mut self, _element: &mut ElementA) -> usize {
+        1
+    }
+
+    fn visit_element_b(&mut self, _element: &mut ElementB) -> usize {
+        1
+    }
+
+    fn visit_composite_element(&mut self, _element: &mut CompositeElement, children: Vec<usize>) -> usize {
+        children.iter().sum::<usize>() + 1
+    }
+
+    fn visit_other(&mut self, _element: &mut dyn Any) -> usize {
+        1
     }
 }
 
@@ -80,24 +824,302 @@ impl Visitor for Visitor2 {
 mod tests {
     use super::*;
 
+    struct Tracer {
+        events: Vec<String>,
+        control: Control,
+    }
+
+    impl Visit for Tracer {
+        fn visit_element_a(&mut self, _element: &ElementA) {
+            self.events.push("a".to_string());
+        }
+
+        fn visit_element_b(&mut self, _element: &ElementB) {
+            self.events.push("b".to_string());
+        }
+
+        fn visit_composite_element(&mut self, _element: &CompositeElement) {
+            self.events.push("composite".to_string());
+        }
+
+        fn enter_composite_element(&mut self, _element: &CompositeElement, depth: usize) -> Control {
+            self.events.push(format!("enter {depth}"));
+            if depth == 0 { Control::Continue } else { self.control }
+        }
+
+        fn leave_composite_element(&mut self, _element: &CompositeElement, depth: usize) {
+            self.events.push(format!("leave {depth}"));
+        }
+    }
+
     #[test]
     fn visitor() {
         let mut elements: Vec<Box<dyn Element>> = vec![
-            Box::new(ElementA),
-            Box::new(ElementB),
+            Box::new(ElementA::default()),
+            Box::new(ElementB::default()),
             Box::new(CompositeElement {
-                children: vec![Box::new(ElementA), Box::new(ElementB)],
+                children: vec![Box::new(ElementA::default()), Box::new(ElementB::default())],
+                ..Default::default()
             }),
         ];
 
-        let mut v1 = Visitor1;
+        let mut v1 = Visitor1::with_writer(Vec::new());
         for e in elements.iter_mut() {
             e.accept(&mut v1);
         }
 
-        let mut v2 = Visitor2;
+        let mut v2 = Visitor2::with_writer(Vec::new());
         for e in elements.iter_mut() {
             e.accept(&mut v2);
         }
+        assert_eq!(String::from_utf8(v2.into_writer()).unwrap(), concat!(
+            "# Visitor2::visit_element_a\n",
+            "# Visitor2::visit_element_b\n",
+            "# Visitor2::visit_element_a\n",
+            "# Visitor2::visit_element_b\n",
+            "# Visitor2::visit_composite_element\n",
+        ));
+
+        for e in elements.iter() {
+            e.accept_ref(&mut v1);
+        }
+        assert_eq!(String::from_utf8(v1.into_writer()).unwrap().lines().count(), 10);
+        let mut v1 = Visitor1::with_writer(Vec::new());
+        Visitor::visit_element_a(&mut v1, &mut ElementA::default());
+        assert_eq!(String::from_utf8(v1.into_writer()).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn value_visitor() {
+        let mut elements: Vec<Box<dyn Element>> = vec![
+            Box::new(ElementA::default()),
+            Box::new(CompositeElement {
+                children: vec![
+                    Box::new(ElementA::default()),
+                    Box::new(CompositeElement {
+                        children: vec![Box::new(ElementB::default()), Box::new(ElementB::default())],
+                        ..Default::default()
+                    }),
+                ],
+                ..Default::default()
+            }),
+        ];
+
+        let counts: Vec<usize> = elements.iter_mut().map(|e| e.accept_value(&mut Counter)).collect();
+        assert_eq!(counts, vec![1, 5]);
+
+        // An element kind unknown to the visitors produces the fallback result of `visit_other`.
+        struct ElementC;
+
+        impl Element for ElementC {
+            fn accept_at(&mut self, visitor: &mut dyn Visitor, _depth: usize) -> Control {
+                visitor.visit_other(self);
+                Control::Continue
+            }
+
+            fn accept_ref_at(&self, visitor: &mut dyn Visit, _depth: usize) -> Control {
+                visitor.visit_other(self);
+                Control::Continue
+            }
+        }
+
+        let mut element: Box<dyn Element> = Box::new(CompositeElement { children: vec![Box::new(ElementC)], ..Default::default() });
+        assert_eq!(element.accept_value(&mut Counter), 2);
+    }
+
+    #[test]
+    fn traversal_control() {
+        let element = CompositeElement {
+            children: vec![
+                Box::new(ElementA::default()),
+                Box::new(CompositeElement { children: vec![Box::new(ElementB::default())], ..Default::default() }),
+                Box::new(ElementA::default()),
+            ],
+            ..Default::default()
+        };
+
+        let mut tracer = Tracer { events: Vec::new(), control: Control::Continue };
+        assert_eq!(element.accept_ref(&mut tracer), Control::Continue);
+        assert_eq!(tracer.events, vec![
+            "enter 0", "a", "enter 1", "b", "composite", "leave 1", "a", "composite", "leave 0",
+        ]);
+
+        let mut tracer = Tracer { events: Vec::new(), control: Control::SkipChildren };
+        assert_eq!(element.accept_ref(&mut tracer), Control::Continue);
+        assert_eq!(tracer.events, vec![
+            "enter 0", "a", "enter 1", "composite", "leave 1", "a", "composite", "leave 0",
+        ]);
+
+        let mut tracer = Tracer { events: Vec::new(), control: Control::Stop };
+        assert_eq!(element.accept_ref(&mut tracer), Control::Stop);
+        assert_eq!(tracer.events, vec!["enter 0", "a", "enter 1"]);
+    }
+
+    #[test]
+    fn walk() {
+        struct CounterB(usize);
+
+        impl Visit for CounterB {
+            fn visit_element_b(&mut self, _element: &ElementB) {
+                self.0 += 1;
+            }
+        }
+
+        // Counts the children of a composite element before visiting them.
+        struct ChildCounter(Vec<usize>);
+
+        impl Visitor for ChildCounter {
+            fn enter_composite_element(&mut self, element: &mut CompositeElement, depth: usize) -> Control {
+                self.0.push(element.children.len());
+                walk_composite_element(self, element, depth);
+                Control::SkipChildren
+            }
+        }
+
+        let mut element = CompositeElement {
+            children: vec![
+                Box::new(ElementB::default()),
+                Box::new(CompositeElement {
+                    children: vec![Box::new(ElementA::default()), Box::new(ElementB::default()), Box::new(ElementB::default())],
+                    ..Default::default()
+                }),
+            ],
+            ..Default::default()
+        };
+
+        let mut counter = CounterB(0);
+        element.accept_ref(&mut counter);
+        assert_eq!(counter.0, 3);
+
+        let mut counter = ChildCounter(Vec::new());
+        element.accept(&mut counter);
+        assert_eq!(counter.0, vec![2, 3]);
+    }
+
+    // Measures the depth of the deepest element.
+    struct Depth;
+
+    impl ValueVisitor<usize> for Depth {
+        fn visit_element_a(&mut self, _element: &mut ElementA) -> usize {
+            1
+        }
+
+        fn visit_element_b(&mut self, _element: &mut ElementB) -> usize {
+            1
+        }
+
+        fn visit_composite_element(&mut self, _element: &mut CompositeElement, children: Vec<usize>) -> usize {
+            children.into_iter().max().unwrap_or(0) + 1
+        }
+
+        fn visit_other(&mut self, _element: &mut dyn Any) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn fan_out() {
+        let element = CompositeElement {
+            children: vec![
+                Box::new(ElementA::default()),
+                Box::new(CompositeElement { children: vec![Box::new(ElementB::default())], ..Default::default() }),
+                Box::new(ElementA::default()),
+            ],
+            ..Default::default()
+        };
+
+        let mut continuing = Tracer { events: Vec::new(), control: Control::Continue };
+        let mut skipping = Tracer { events: Vec::new(), control: Control::SkipChildren };
+        let mut stopping = Tracer { events: Vec::new(), control: Control::Stop };
+        let mut fan_out: FanOut<dyn Visit> = FanOut::new(vec![&mut continuing, &mut skipping, &mut stopping]);
+        assert_eq!(element.accept_ref(&mut fan_out), Control::Continue);
+        assert_eq!(continuing.events, vec![
+            "enter 0", "a", "enter 1", "b", "composite", "leave 1", "a", "composite", "leave 0",
+        ]);
+        assert_eq!(skipping.events, vec![
+            "enter 0", "a", "enter 1", "composite", "leave 1", "a", "composite", "leave 0",
+        ]);
+        assert_eq!(stopping.events, vec!["enter 0", "a", "enter 1"]);
+
+        let mut first = Tracer { events: Vec::new(), control: Control::Stop };
+        let mut second = Tracer { events: Vec::new(), control: Control::Stop };
+        let mut fan_out: FanOut<dyn Visit> = FanOut::new(vec![&mut first, &mut second]);
+        assert_eq!(element.accept_ref(&mut fan_out), Control::Stop);
+        assert_eq!(first.events, second.events);
+
+        let mut elements: Vec<Box<dyn Element>> = vec![
+            Box::new(ElementA::default()),
+            Box::new(CompositeElement {
+                children: vec![Box::new(ElementA::default()), Box::new(CompositeElement { children: vec![Box::new(ElementB::default())], ..Default::default() })],
+                ..Default::default()
+            }),
+        ];
+        let mut counter = Counter;
+        let mut depth = Depth;
+        let mut fan_out: FanOut<dyn ValueVisitor<usize>> = FanOut::new(vec![&mut counter, &mut depth]);
+        let results: Vec<Vec<usize>> = elements.iter_mut().map(|e| e.accept_value(&mut fan_out)).collect();
+        assert_eq!(results, vec![vec![1, 1], vec![4, 3]]);
+    }
+    #[test]
+    fn accept_all() {
+        let mut elements: Vec<Box<dyn Element>> = vec![
+            Box::new(ElementA::new("a1")),
+            Box::new(CompositeElement::new("root").with_child(ElementB::new("b1")).with_child(ElementA::new("a2"))),
+        ];
+
+        let mut v2 = Visitor2::with_writer(Vec::new());
+        elements.iter_mut().accept_all(&mut v2);
+        assert_eq!(String::from_utf8(v2.into_writer()).unwrap().lines().count(), 4);
+
+        let tracer = elements.iter().visit_with(TracingVisitor::new(Vec::new()));
+        let payloads: Vec<&str> = tracer.sink().iter().filter(|e| e.phase == Phase::Visit).map(|e| e.payload.as_str()).collect();
+        assert_eq!(payloads, vec!["a1", "b1", "a2", "root"]);
+
+        let v1 = elements.iter_mut().visit_with::<_, ByVisitor>(Visitor1::with_writer(Vec::new()));
+        assert_eq!(String::from_utf8(v1.into_writer()).unwrap().lines().count(), 4);
+        assert_eq!(elements.iter_mut().accept_each(&mut Counter), vec![1, 3]);
+    }
+
+    #[test]
+    fn tracing_visitor() {
+        let mut inner = CompositeElement::new("inner").with_child(ElementB::new("b1"));
+        inner.add(Box::new(ElementA::new("spare")));
+        assert!(inner.remove(1).is_some() && inner.remove(1).is_none());
+        let mut root = CompositeElement::new("root").with_child(ElementA::new("a1")).with_child(inner);
+        root.add(Box::new(ElementB::new("b2")));
+        assert_eq!((root.payload(), root.children().len()), ("root", 3));
+
+        let mut tracer = TracingVisitor::new(Vec::new());
+        assert_eq!(root.accept_ref(&mut tracer), Control::Continue);
+        let events: Vec<(usize, usize, Phase, ElementKind, &str)> = tracer.sink().iter().map(|e| (e.order, e.depth, e.phase, e.kind, e.payload.as_str())).collect();
+        assert_eq!(events, vec![
+            (0, 0, Phase::Enter, ElementKind::Composite, "root"),
+            (1, 1, Phase::Visit, ElementKind::A, "a1"),
+            (2, 1, Phase::Enter, ElementKind::Composite, "inner"),
+            (3, 2, Phase::Visit, ElementKind::B, "b1"),
+            (4, 1, Phase::Visit, ElementKind::Composite, "inner"),
+            (5, 1, Phase::Leave, ElementKind::Composite, "inner"),
+            (6, 1, Phase::Visit, ElementKind::B, "b2"),
+            (7, 0, Phase::Visit, ElementKind::Composite, "root"),
+            (8, 0, Phase::Leave, ElementKind::Composite, "root"),
+        ]);
+
+        // A composite visited without being entered does not underflow the depth.
+        let mut tracer = TracingVisitor::new(Vec::new());
+        Visit::visit_composite_element(&mut tracer, &root);
+        Visit::visit_element_a(&mut tracer, &ElementA::new("a2"));
+        let events: Vec<(usize, &str)> = tracer.sink().iter().map(|e| (e.depth, e.payload.as_str())).collect();
+        assert_eq!(events, vec![(0, "root"), (0, "a2")]);
+
+        let mut tracer = TracingVisitor::new(WriterSink::new(Vec::new()));
+        root.accept(&mut tracer);
+        let lines = String::from_utf8(tracer.into_sink().into_writer()).unwrap();
+        assert_eq!(lines.lines().take(4).collect::<Vec<_>>(), vec!["0 enter composite root", "1   visit a a1", "2   enter composite inner", "3     visit b b1"]);
+
+        let mut leaves = Vec::new();
+        let mut tracer = TracingVisitor::new(CallbackSink::new(|event: TraceEvent| if event.kind != ElementKind::Composite { leaves.push(event.payload) }));
+        root.accept_ref(&mut tracer);
+        ElementA::default().accept_ref(&mut tracer);
+        assert_eq!(leaves, vec!["a1", "b1", "b2", ""]);
     }
 }